@@ -8,11 +8,41 @@ pub struct Ident {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Type {
-    Int { span: Span },
-    Bool { span: Span },
-    Unit { span: Span },
-    Secret { inner: Box<Type>, span: Span },
-    Named { name: String, span: Span },
+    Int {
+        span: Span,
+    },
+    Bool {
+        span: Span,
+    },
+    Unit {
+        span: Span,
+    },
+    Str {
+        span: Span,
+    },
+    Secret {
+        inner: Box<Type>,
+        span: Span,
+    },
+    /// `labeled<internal> T`: `T` carrying a label declared with `label ... ;`.
+    Labeled {
+        label: Ident,
+        inner: Box<Type>,
+        span: Span,
+    },
+    Named {
+        name: String,
+        span: Span,
+    },
+    /// `Bytes[N]`: fixed-size byte array; `len` must be a compile-time constant.
+    Bytes {
+        len: Box<Expr>,
+        span: Span,
+    },
+    /// Placeholder for a type that failed to parse (already reported).
+    Error {
+        span: Span,
+    },
 }
 
 impl Type {
@@ -21,8 +51,11 @@ impl Type {
             Type::Int { span }
             | Type::Bool { span }
             | Type::Unit { span }
-            | Type::Secret { span, .. } => span,
-            Type::Named { span, .. } => span,
+            | Type::Str { span }
+            | Type::Secret { span, .. }
+            | Type::Labeled { span, .. } => span,
+            Type::Named { span, .. } | Type::Bytes { span, .. } => span,
+            Type::Error { span } => span,
         }
     }
 }
@@ -33,6 +66,20 @@ pub enum Effect {
     Net,
     Time,
     Rand,
+    /// Turning a secret into a public value with `declassify(x, "reason")`.
+    Declassify,
+}
+
+impl Effect {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Effect::Io => "io",
+            Effect::Net => "net",
+            Effect::Time => "time",
+            Effect::Rand => "rand",
+            Effect::Declassify => "declassify",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -43,21 +90,135 @@ pub struct EffectSpec {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Program {
+    pub imports: Vec<ImportDecl>,
+    pub structs: Vec<StructDecl>,
+    pub enums: Vec<EnumDecl>,
+    pub aliases: Vec<TypeAlias>,
+    pub consts: Vec<ConstDecl>,
+    pub labels: Vec<LabelDecl>,
     pub funcs: Vec<FnDecl>,
     pub protocols: Vec<ProtocolDecl>,
     pub span: Span,
 }
 
+/// `import "dir/file.candy";` or `mod name;` (which means `import "name.candy";`).
+/// Paths are relative to the importing file; `name` is the module's local name,
+/// used as the qualifier in `name::item`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportDecl {
+    pub path: String,
+    pub name: Ident,
+    pub span: Span,
+}
+
+/// `struct KeyPair { pub_key: Int, priv_key: secret Int }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructDecl {
+    pub docs: Vec<String>,
+    pub name: Ident,
+    pub fields: Vec<FieldDecl>,
+    pub span: Span,
+}
+
+/// `type Key = secret Int;`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeAlias {
+    pub docs: Vec<String>,
+    pub name: Ident,
+    pub ty: Type,
+    pub span: Span,
+}
+
+/// `const ROUNDS: Int = 10;` (the initializer is evaluated at compile time)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstDecl {
+    pub docs: Vec<String>,
+    pub name: Ident,
+    pub ty: Type,
+    pub expr: Expr,
+    pub span: Span,
+}
+
+/// `label public < internal < secret;`: the security labels, lowest first. Unlabeled
+/// types carry the lowest label; `secret T` carries the highest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelDecl {
+    pub docs: Vec<String>,
+    pub names: Vec<Ident>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDecl {
+    pub name: Ident,
+    pub ty: Type,
+    pub span: Span,
+}
+
+/// `enum Outcome { Ok(Int), Denied }`; variants are named `Outcome::Ok` in expressions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnumDecl {
+    pub docs: Vec<String>,
+    pub name: Ident,
+    pub variants: Vec<VariantDecl>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariantDecl {
+    pub name: Ident,
+    pub payload: Vec<Type>, // empty for a unit variant
+    pub span: Span,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FnDecl {
+    pub docs: Vec<String>, // `///` lines directly above the item
     pub name: Ident,
+    pub generics: Vec<GenericParam>, // `<T, U: Copy>`
     pub params: Vec<Param>,
     pub ret: Type,
     pub effects: Vec<EffectSpec>, // default pure when empty
+    /// Marked `ct`: the body is checked to run in time independent of its secrets.
+    pub ct: bool,
+    pub attrs: Vec<Attribute>, // `#[allow(...)]` etc. above the item
     pub body: Block,
     pub span: Span,
 }
 
+/// `#[allow(unused-variable, ...)]`: sets the level of lint rules inside one function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute {
+    pub level: LintLevel,
+    pub rules: Vec<Ident>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl LintLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LintLevel::Allow => "allow",
+            LintLevel::Warn => "warn",
+            LintLevel::Deny => "deny",
+        }
+    }
+}
+
+/// A type parameter `T` with optional bounds (`T: Copy`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenericParam {
+    pub name: Ident,
+    pub bounds: Vec<Ident>,
+    pub span: Span,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Param {
     pub name: Ident,
@@ -75,10 +236,17 @@ pub struct Block {
 pub enum Stmt {
     Let {
         name: Ident,
+        mutable: bool, // `let mut`
         ty: Option<Type>,
         expr: Expr,
         span: Span,
     },
+    /// `name = expr;` (only `let mut` bindings).
+    Assign {
+        name: Ident,
+        expr: Expr,
+        span: Span,
+    },
     Return {
         expr: Option<Expr>,
         span: Span,
@@ -89,10 +257,78 @@ pub enum Stmt {
         else_blk: Option<Block>,
         span: Span,
     },
+    /// `for var in start..end { body }`: half-open range with compile-time bounds.
+    For {
+        var: Ident,
+        start: Expr,
+        end: Expr,
+        body: Block,
+        span: Span,
+    },
+    /// `match scrutinee { pattern => { ... } ... }`
+    Match {
+        scrutinee: Expr,
+        arms: Vec<MatchArm>,
+        span: Span,
+    },
     Expr {
         expr: Expr,
         span: Span,
     },
+    /// Statement that failed to parse (already reported); skipped by later passes.
+    Error {
+        span: Span,
+    },
+}
+
+impl Stmt {
+    pub fn span(&self) -> &Span {
+        match self {
+            Stmt::Let { span, .. }
+            | Stmt::Assign { span, .. }
+            | Stmt::Return { span, .. }
+            | Stmt::If { span, .. }
+            | Stmt::For { span, .. }
+            | Stmt::Match { span, .. }
+            | Stmt::Expr { span, .. }
+            | Stmt::Error { span } => span,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: Block,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    /// `Outcome::Ok(x)` / `Outcome::Denied`; `_` bindings are named "_".
+    Variant {
+        path: Ident,
+        bindings: Vec<Ident>,
+        span: Span,
+    },
+    BoolLit {
+        value: bool,
+        span: Span,
+    },
+    /// `_`
+    Wildcard {
+        span: Span,
+    },
+}
+
+impl Pattern {
+    pub fn span(&self) -> &Span {
+        match self {
+            Pattern::Variant { span, .. }
+            | Pattern::BoolLit { span, .. }
+            | Pattern::Wildcard { span } => span,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -114,8 +350,10 @@ pub enum Expr {
         name: Ident,
         span: Span,
     },
+    /// `move(x)` or a partial move `move(x.a.b)` (`fields` = `[a, b]`).
     Move {
         name: Ident,
+        fields: Vec<Ident>,
         span: Span,
     },
 
@@ -124,6 +362,137 @@ pub enum Expr {
         args: Vec<Expr>,
         span: Span,
     },
+
+    /// `base.field`
+    Field {
+        base: Box<Expr>,
+        field: Ident,
+        span: Span,
+    },
+    /// `base[index]`
+    Index {
+        base: Box<Expr>,
+        index: Box<Expr>,
+        span: Span,
+    },
+    /// `base[start..end]` (constant bounds)
+    Slice {
+        base: Box<Expr>,
+        start: Box<Expr>,
+        end: Box<Expr>,
+        span: Span,
+    },
+    /// `[e1, e2, ...]`
+    ArrayLit {
+        elems: Vec<Expr>,
+        span: Span,
+    },
+    /// `[elem; count]`
+    ArrayRepeat {
+        elem: Box<Expr>,
+        count: Box<Expr>,
+        span: Span,
+    },
+    /// `Name { field: expr, ... }`
+    StructLit {
+        name: Ident,
+        fields: Vec<FieldInit>,
+        span: Span,
+    },
+
+    Unary {
+        op: UnOp,
+        expr: Box<Expr>,
+        span: Span,
+    },
+    Binary {
+        op: BinOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+        span: Span,
+    },
+
+    /// Expression that failed to parse (already reported); typed as unknown.
+    Error {
+        span: Span,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldInit {
+    pub name: Ident,
+    pub expr: Expr,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnOp {
+    Neg, // -
+    Not, // !
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+impl UnOp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UnOp::Neg => "-",
+            UnOp::Not => "!",
+        }
+    }
+}
+
+impl BinOp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Rem => "%",
+            BinOp::Eq => "==",
+            BinOp::Ne => "!=",
+            BinOp::Lt => "<",
+            BinOp::Le => "<=",
+            BinOp::Gt => ">",
+            BinOp::Ge => ">=",
+            BinOp::And => "&&",
+            BinOp::Or => "||",
+        }
+    }
+
+    pub fn is_arithmetic(self) -> bool {
+        matches!(
+            self,
+            BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Rem
+        )
+    }
+
+    pub fn is_comparison(self) -> bool {
+        matches!(
+            self,
+            BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge
+        )
+    }
+
+    pub fn is_logical(self) -> bool {
+        matches!(self, BinOp::And | BinOp::Or)
+    }
 }
 
 impl Expr {
@@ -135,6 +504,15 @@ impl Expr {
             Expr::Var { span, .. } => span,
             Expr::Move { span, .. } => span,
             Expr::Call { span, .. } => span,
+            Expr::Field { span, .. } => span,
+            Expr::Index { span, .. } => span,
+            Expr::Slice { span, .. } => span,
+            Expr::ArrayLit { span, .. } => span,
+            Expr::ArrayRepeat { span, .. } => span,
+            Expr::StructLit { span, .. } => span,
+            Expr::Unary { span, .. } => span,
+            Expr::Binary { span, .. } => span,
+            Expr::Error { span } => span,
         }
     }
 }
@@ -143,6 +521,7 @@ impl Expr {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ProtocolDecl {
+    pub docs: Vec<String>,
     pub name: Ident,
     pub states: Vec<StateDecl>,
     pub transitions: Vec<TransitionDecl>,
@@ -151,6 +530,7 @@ pub struct ProtocolDecl {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct StateDecl {
+    pub docs: Vec<String>,
     pub name: Ident,
     pub is_final: bool,
     pub span: Span,
@@ -158,6 +538,7 @@ pub struct StateDecl {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TransitionDecl {
+    pub docs: Vec<String>,
     pub from: Ident,
     pub to: Ident,
     pub span: Span,
@@ -174,3 +555,6 @@ pub fn ty_bool(span: Span) -> Type {
 pub fn ty_unit(span: Span) -> Type {
     Type::Unit { span }
 }
+pub fn ty_str(span: Span) -> Type {
+    Type::Str { span }
+}
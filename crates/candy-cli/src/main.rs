@@ -1,12 +1,13 @@
 use std::fs;
 
 use candy_diagnostics::{Diagnostic, DiagnosticReport, Span};
-use candy_parser::parse_file;
-use candy_typecheck::typecheck;
+use candy_lint::lint;
+use candy_parser::parse_file_with_imports;
+use candy_typecheck::{declassifications, typecheck_with_warnings, Declassification};
 
 fn print_usage() {
     eprintln!(
-        "Candy 🍭\n\nUSAGE:\n  candy check [--agent] <file.candy>\n\nFLAGS:\n  --agent   Output diagnostics as JSON ONLY (stdout)\n"
+        "Candy 🍭\n\nUSAGE:\n  candy check [--agent] [--declassifications] <file.candy>\n\nFLAGS:\n  --agent               Output diagnostics as JSON ONLY (stdout)\n  --declassifications   Also list every declassify(...) site with its reason\n"
     );
 }
 
@@ -29,6 +30,32 @@ fn render_human(report: &DiagnosticReport) {
     }
 }
 
+fn render_declassifications(sites: &[Declassification]) {
+    println!("{} declassification(s)", sites.len());
+    for d in sites {
+        println!(
+            "  {}:{}:{} in `{}`: \"{}\"",
+            d.span.file, d.span.start_line, d.span.start_col, d.function, d.reason
+        );
+    }
+}
+
+/// Agent mode with `--declassifications`: the usual report plus a `declassifications` array.
+fn declassifications_json(report: &DiagnosticReport, sites: &[Declassification]) -> String {
+    let mut v = serde_json::to_value(report).expect("diagnostic JSON serialization must not fail");
+    v["declassifications"] = sites
+        .iter()
+        .map(|d| {
+            serde_json::json!({
+                "function": d.function,
+                "reason": d.reason,
+                "span": d.span,
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&v).expect("diagnostic JSON serialization must not fail")
+}
+
 fn main() {
     let mut args: Vec<String> = std::env::args().collect();
 
@@ -44,12 +71,15 @@ fn main() {
     }
 
     let mut agent = false;
+    let mut list_declassifications = false;
     let mut file: Option<String> = None;
 
     let rest = args.drain(2..).collect::<Vec<_>>();
     for a in rest {
         if a == "--agent" {
             agent = true;
+        } else if a == "--declassifications" {
+            list_declassifications = true;
         } else if a.starts_with('-') {
             eprintln!("Unknown flag: {}", a);
             print_usage();
@@ -86,29 +116,43 @@ fn main() {
 
     let mut report = DiagnosticReport::new();
 
-    let program = match parse_file(&path, &src) {
-        Ok(p) => p,
-        Err(mut r) => {
-            report.diagnostics.append(&mut r.diagnostics);
-            if agent {
-                println!("{}", report.to_json_pretty());
-            } else {
-                render_human(&report);
-            }
-            std::process::exit(1);
-        }
-    };
-
-    if let Err(mut r) = typecheck(&program) {
-        report.diagnostics.append(&mut r.diagnostics);
+    // Parse errors do not stop the pipeline: the typechecker still reports semantic
+    // errors in the parts that parsed, so agents see everything in one round-trip.
+    // Imported modules are resolved relative to `path` and merged into one program.
+    let (program, mut parse_report) = parse_file_with_imports(&path, &src);
+    report.diagnostics.append(&mut parse_report.diagnostics);
+
+    report
+        .diagnostics
+        .append(&mut typecheck_with_warnings(&program).diagnostics);
+    // Lints are about style: they only run on programs that are otherwise correct.
+    if report.is_ok() {
+        report.diagnostics.append(&mut lint(&program).diagnostics);
     }
 
+    let sites = if list_declassifications {
+        Some(declassifications(&program))
+    } else {
+        None
+    };
+
     if agent {
-        println!("{}", report.to_json_pretty());
-    } else if report.is_ok() {
-        eprintln!("ok");
+        match &sites {
+            Some(sites) => println!("{}", declassifications_json(&report, sites)),
+            None => println!("{}", report.to_json_pretty()),
+        }
     } else {
+        // Warnings are shown even when the check passes.
         render_human(&report);
+        if report.is_ok() {
+            eprintln!("ok");
+        }
+    }
+
+    if !agent {
+        if let Some(sites) = &sites {
+            render_declassifications(sites);
+        }
     }
 
     std::process::exit(if report.is_ok() { 0 } else { 1 });
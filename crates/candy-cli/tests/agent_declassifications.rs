@@ -0,0 +1,63 @@
+use std::process::Command;
+
+fn write_temp(src: &str) -> tempfile::NamedTempFile {
+    let mut f = tempfile::NamedTempFile::new().expect("tempfile");
+    std::io::Write::write_all(&mut f, src.as_bytes()).expect("write");
+    f
+}
+
+const SRC: &str = r#"
+fn main() -> Unit effects(declassify) {
+  let t: secret Bool = true;
+  let _ok: Bool = declassify(t, "MAC verification result");
+  return;
+}
+"#;
+
+#[test]
+fn agent_report_lists_declassifications() {
+    let f = write_temp(SRC);
+    let out = Command::new(env!("CARGO_BIN_EXE_candy"))
+        .args([
+            "check",
+            "--agent",
+            "--declassifications",
+            f.path().to_str().unwrap(),
+        ])
+        .output()
+        .expect("run candy");
+
+    assert!(out.status.success());
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).expect("stdout is json");
+    assert_eq!(v["diagnostics"].as_array().unwrap().len(), 0);
+    let sites = v["declassifications"].as_array().unwrap();
+    assert_eq!(sites.len(), 1);
+    assert_eq!(sites[0]["function"], "main");
+    assert_eq!(sites[0]["reason"], "MAC verification result");
+    assert_eq!(sites[0]["span"]["start_line"], 4);
+}
+
+#[test]
+fn human_report_lists_declassifications() {
+    let f = write_temp(SRC);
+    let out = Command::new(env!("CARGO_BIN_EXE_candy"))
+        .args(["check", "--declassifications", f.path().to_str().unwrap()])
+        .output()
+        .expect("run candy");
+
+    assert!(out.status.success());
+    let s = String::from_utf8(out.stdout).unwrap();
+    assert!(s.starts_with("1 declassification(s)"), "{s}");
+    assert!(s.contains("in `main`: \"MAC verification result\""), "{s}");
+}
+
+#[test]
+fn agent_output_is_unchanged_without_the_flag() {
+    let f = write_temp(SRC);
+    let out = Command::new(env!("CARGO_BIN_EXE_candy"))
+        .args(["check", "--agent", f.path().to_str().unwrap()])
+        .output()
+        .expect("run candy");
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).expect("stdout is json");
+    assert!(v.get("declassifications").is_none());
+}
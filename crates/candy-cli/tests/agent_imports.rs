@@ -0,0 +1,45 @@
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn check_follows_imports_and_reports_errors_in_the_right_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let main = dir.path().join("main.candy");
+    fs::write(
+        &main,
+        r#"
+import "lib/keys.candy";
+fn main() -> Unit {
+  let k: Int = keys::derive();
+  return;
+}
+"#,
+    )
+    .unwrap();
+    fs::create_dir_all(dir.path().join("lib")).unwrap();
+    fs::write(
+        dir.path().join("lib/keys.candy"),
+        "fn derive() -> Int {\n  log(\"x\");\n  return 1;\n}\n",
+    )
+    .unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_candy"))
+        .args(["check", "--agent", main.to_str().unwrap()])
+        .output()
+        .expect("run candy");
+
+    assert!(!out.status.success());
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).expect("stdout is json");
+    let diags = v["diagnostics"].as_array().unwrap();
+    assert_eq!(diags.len(), 1, "{v}");
+    assert_eq!(diags[0]["code"], "undeclared-effect");
+    assert!(diags[0]["span"]["file"]
+        .as_str()
+        .unwrap()
+        .ends_with("keys.candy"));
+    assert_eq!(diags[0]["span"]["start_line"], 2);
+    assert_eq!(
+        diags[0]["fix"]["replace"], "fn derive(...) -> Int {",
+        "fix must quote the source, not the qualified name"
+    );
+}
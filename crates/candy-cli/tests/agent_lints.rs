@@ -0,0 +1,59 @@
+use std::process::Command;
+
+fn write_temp(src: &str) -> tempfile::NamedTempFile {
+    let mut f = tempfile::NamedTempFile::new().expect("tempfile");
+    std::io::Write::write_all(&mut f, src.as_bytes()).expect("write");
+    f
+}
+
+fn check(src: &str) -> (bool, serde_json::Value) {
+    let f = write_temp(src);
+    let out = Command::new(env!("CARGO_BIN_EXE_candy"))
+        .args(["check", "--agent", f.path().to_str().unwrap()])
+        .output()
+        .expect("run candy");
+    let v = serde_json::from_slice(&out.stdout).expect("stdout is json");
+    (out.status.success(), v)
+}
+
+#[test]
+fn lint_warnings_do_not_fail_the_check() {
+    let (ok, v) = check("fn main() -> Unit { let x: Int = 1; return; }");
+    assert!(ok);
+    let d = &v["diagnostics"][0];
+    assert_eq!(d["code"], "unused-variable");
+    assert_eq!(d["severity"], "Warning");
+}
+
+#[test]
+fn denied_lint_fails_the_check() {
+    let (ok, v) = check("#[deny(unused-variable)]\nfn main() -> Unit { let x: Int = 1; return; }");
+    assert!(!ok);
+    assert_eq!(v["diagnostics"][0]["severity"], "Error");
+}
+
+#[test]
+fn lints_wait_for_a_correct_program() {
+    let (ok, v) = check("fn main() -> Unit { let x: Int = true; return; }");
+    assert!(!ok);
+    let codes: Vec<&str> = v["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| d["code"].as_str().unwrap())
+        .collect();
+    assert_eq!(codes, vec!["type-mismatch"]);
+}
+
+#[test]
+fn typecheck_warnings_show_on_a_passing_check() {
+    let (ok, v) = check("fn main() -> Unit effects() { return; let x: Int = 1; }");
+    assert!(ok);
+    let codes: Vec<&str> = v["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| d["code"].as_str().unwrap())
+        .collect();
+    assert_eq!(codes, vec!["unreachable-code", "unused-variable"]);
+}
@@ -0,0 +1,42 @@
+use std::process::Command;
+
+fn write_temp(src: &str) -> tempfile::NamedTempFile {
+    let mut f = tempfile::NamedTempFile::new().expect("tempfile");
+    std::io::Write::write_all(&mut f, src.as_bytes()).expect("write");
+    f
+}
+
+#[test]
+fn agent_reports_parse_and_type_errors_together() {
+    let src = r#"
+fn broken() -> Unit {
+  let x = ;
+  return;
+}
+
+fn main() -> Unit {
+  let a: secret Int = 1;
+  let b: secret Int = a;
+  wipe(b);
+  return;
+
+}
+"#;
+    let f = write_temp(src);
+
+    let out = Command::new(env!("CARGO_BIN_EXE_candy"))
+        .args(["check", "--agent", f.path().to_str().unwrap()])
+        .output()
+        .expect("run candy");
+
+    assert!(!out.status.success());
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).expect("stdout is json");
+    let codes: Vec<&str> = v["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|d| d["code"].as_str())
+        .collect();
+
+    assert_eq!(codes, vec!["parse-unexpected-token", "secret-copy"]);
+}
@@ -37,6 +37,17 @@ impl Span {
             end_col: col,
         }
     }
+
+    /// Span from the start of `self` to the end of `end` (same file assumed).
+    pub fn join(&self, end: &Span) -> Self {
+        Self {
+            file: self.file.clone(),
+            start_line: self.start_line,
+            start_col: self.start_col,
+            end_line: end.end_line,
+            end_col: end.end_col,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -1,4 +1,4 @@
-use candy_diagnostics::Span;
+use candy_diagnostics::{Diagnostic, DiagnosticReport, Span};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TokenKind {
@@ -10,24 +10,58 @@ pub enum TokenKind {
     Ident(String),
     IntLit(i64),
     StrLit(String),
+    /// `/// text` doc comment; the payload is the text after the marker.
+    DocComment(String),
 
     KwFn,
     KwLet,
+    KwMut,
     KwReturn,
     KwSecret,
     KwIf,
     KwElse,
+    KwFor,
+    KwIn,
     KwEffects,
+    KwImport,
+    KwMod,
+    KwStruct,
+    KwEnum,
+    KwMatch,
+    KwType,
+    KwConst,
 
     LParen,
     RParen,
     LBrace,
     RBrace,
+    LBracket,
+    RBracket,
     Colon,
+    ColonColon, // ::
+    Dot,        // .
+    DotDot,     // ..
     Semi,
     Comma,
     Eq,
-    Arrow, // ->
+    Arrow,    // ->
+    FatArrow, // =>
+    Hash,     // # (starts an attribute `#[...]`)
+
+    Plus,    // +
+    Minus,   // -
+    Star,    // *
+    Slash,   // /
+    Percent, // %
+    EqEq,    // ==
+    NotEq,   // !=
+    Lt,      // <
+    Le,      // <=
+    Gt,      // >
+    Ge,      // >=
+    AndAnd,  // &&
+    OrOr,    // ||
+    Bang,    // !
 
     Eof,
 }
@@ -45,6 +79,7 @@ pub struct Lexer<'a> {
     i: usize,  // byte offset
     line: u32, // 1-based
     col: u32,  // 1-based
+    report: DiagnosticReport,
 }
 
 impl<'a> Lexer<'a> {
@@ -55,10 +90,16 @@ impl<'a> Lexer<'a> {
             i: 0,
             line: 1,
             col: 1,
+            report: DiagnosticReport::new(),
         }
     }
 
-    pub fn lex_all(mut self) -> Vec<Token> {
+    pub fn lex_all(self) -> Vec<Token> {
+        self.lex_all_with_report().0
+    }
+
+    /// Like `lex_all`, but also returns the lexical diagnostics.
+    pub fn lex_all_with_report(mut self) -> (Vec<Token>, DiagnosticReport) {
         let mut out = Vec::new();
         loop {
             let t = self.next_token();
@@ -68,7 +109,20 @@ impl<'a> Lexer<'a> {
                 break;
             }
         }
-        out
+        (out, self.report)
+    }
+
+    /// Lexical errors recorded so far. The lexer never fails: it reports and keeps going.
+    pub fn report(&self) -> &DiagnosticReport {
+        &self.report
+    }
+
+    pub fn take_report(&mut self) -> DiagnosticReport {
+        std::mem::take(&mut self.report)
+    }
+
+    fn err(&mut self, code: &str, msg: impl Into<String>, span: Span) {
+        self.report.push(Diagnostic::error(code, msg, span));
     }
 
     fn peek(&self) -> Option<char> {
@@ -99,12 +153,96 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    fn peek_at(&self, n: usize) -> Option<char> {
+        self.src[self.i..].chars().nth(n)
+    }
+
+    /// Skips whitespace, `//` line comments and (nested) `/* */` block comments.
+    /// Stops in front of a `///` doc comment so it can be emitted as a token.
     fn skip_ws(&mut self) {
-        while matches!(self.peek(), Some(ch) if ch.is_whitespace()) {
+        loop {
+            match (self.peek(), self.peek_at(1)) {
+                (Some(ch), _) if ch.is_whitespace() => {
+                    self.bump();
+                }
+                (Some('/'), Some('/')) => {
+                    if self.at_doc_comment() {
+                        return;
+                    }
+                    self.skip_line_comment();
+                }
+                (Some('/'), Some('*')) => self.skip_block_comment(),
+                _ => return,
+            }
+        }
+    }
+
+    /// `///` starts a doc comment, but `////...` is an ordinary comment (as in Rust).
+    fn at_doc_comment(&self) -> bool {
+        self.src[self.i..].starts_with("///") && self.peek_at(3) != Some('/')
+    }
+
+    fn skip_line_comment(&mut self) {
+        while matches!(self.peek(), Some(ch) if ch != '\n') {
             self.bump();
         }
     }
 
+    fn skip_block_comment(&mut self) {
+        // current is "/*"
+        let (sl, sc) = (self.line, self.col);
+        self.bump();
+        self.bump();
+        let mut depth = 1u32;
+
+        while depth > 0 {
+            match (self.peek(), self.peek_at(1)) {
+                (Some('/'), Some('*')) => {
+                    self.bump();
+                    self.bump();
+                    depth += 1;
+                }
+                (Some('*'), Some('/')) => {
+                    self.bump();
+                    self.bump();
+                    depth -= 1;
+                }
+                (Some(_), _) => {
+                    self.bump();
+                }
+                (None, _) => {
+                    let sp = self.mk_span(sl, sc, self.line, self.col);
+                    self.err(
+                        "lex-unterminated-comment",
+                        "Unterminated block comment (missing `*/`).",
+                        sp,
+                    );
+                    return;
+                }
+            }
+        }
+    }
+
+    fn lex_doc_comment(&mut self, sl: u32, sc: u32) -> Token {
+        // current is "///"
+        self.bump();
+        self.bump();
+        self.bump();
+        let mut s = String::new();
+        while let Some(ch) = self.peek() {
+            if ch == '\n' {
+                break;
+            }
+            s.push(self.bump().unwrap());
+        }
+
+        let text = s.strip_prefix(' ').unwrap_or(&s).trim_end().to_string();
+        Token {
+            kind: TokenKind::DocComment(text),
+            span: self.mk_span(sl, sc, self.line, self.col),
+        }
+    }
+
     fn lex_string(&mut self, sl: u32, sc: u32) -> Token {
         // current is '"'
         self.bump(); // consume opening quote
@@ -119,123 +257,385 @@ impl<'a> Lexer<'a> {
                 };
             }
 
-            // v0.4 minimal: no escapes. newline ends string (error recovery as Ident)
+            // Strings are single-line; a newline means the closing quote is missing.
             if ch == '\n' {
                 break;
             }
 
+            if ch == '\\' {
+                if let Some(c) = self.lex_escape() {
+                    s.push(c);
+                }
+                continue;
+            }
+
             s.push(self.bump().unwrap());
         }
 
-        // Unterminated string: recover by emitting Ident token to avoid lexer failure.
+        // Unterminated string: keep the text read so far so parsing can continue.
+        let sp = self.mk_span(sl, sc, self.line, self.col);
+        self.err(
+            "lex-unterminated-string",
+            "Unterminated string literal (missing closing `\"`).",
+            sp.clone(),
+        );
         Token {
-            kind: TokenKind::Ident("\"".into()),
+            kind: TokenKind::StrLit(s),
+            span: sp,
+        }
+    }
+
+    /// Decodes one escape sequence; current is `\`. Invalid escapes are reported
+    /// and yield `None` (the offending text is dropped from the literal).
+    fn lex_escape(&mut self) -> Option<char> {
+        let (sl, sc) = (self.line, self.col);
+        self.bump(); // consume '\\'
+
+        let decoded = match self.peek() {
+            Some('n') => Some('\n'),
+            Some('t') => Some('\t'),
+            Some('r') => Some('\r'),
+            Some('0') => Some('\0'),
+            Some('\\') => Some('\\'),
+            Some('"') => Some('"'),
+            Some('u') => {
+                self.bump(); // consume 'u'
+                return self.lex_unicode_escape(sl, sc);
+            }
+            _ => None,
+        };
+
+        // Never swallow the newline: the string is unterminated and that gets its own error.
+        if matches!(self.peek(), Some(c) if c != '\n') {
+            self.bump();
+        }
+        if decoded.is_none() {
+            let sp = self.mk_span(sl, sc, self.line, self.col);
+            self.err(
+                "lex-invalid-escape",
+                "Invalid escape sequence (expected \\n, \\t, \\r, \\0, \\\\, \\\" or \\u{...}).",
+                sp,
+            );
+        }
+        decoded
+    }
+
+    /// `\u{XXXX}`: 1 to 6 hex digits naming a Unicode scalar value.
+    fn lex_unicode_escape(&mut self, sl: u32, sc: u32) -> Option<char> {
+        let mut digits = String::new();
+        let mut closed = false;
+
+        if self.peek() == Some('{') {
+            self.bump();
+            while let Some(c) = self.peek() {
+                if c == '}' {
+                    self.bump();
+                    closed = true;
+                    break;
+                }
+                if !c.is_ascii_hexdigit() {
+                    break;
+                }
+                digits.push(c);
+                self.bump();
+            }
+        }
+
+        let decoded = if closed && (1..=6).contains(&digits.len()) {
+            u32::from_str_radix(&digits, 16)
+                .ok()
+                .and_then(char::from_u32)
+        } else {
+            None
+        };
+
+        if decoded.is_none() {
+            let sp = self.mk_span(sl, sc, self.line, self.col);
+            self.err(
+                "lex-invalid-escape",
+                "Invalid unicode escape (expected \\u{...} with 1-6 hex digits naming a valid character).",
+                sp,
+            );
+        }
+        decoded
+    }
+
+    /// `r"..."` or `r#"..."#` (any number of `#`); no escapes, may span lines.
+    /// Current is 'r'.
+    fn lex_raw_string(&mut self, sl: u32, sc: u32) -> Token {
+        self.bump(); // consume 'r'
+        let mut hashes = 0usize;
+        while self.peek() == Some('#') {
+            self.bump();
+            hashes += 1;
+        }
+        self.bump(); // consume opening quote
+
+        let mut s = String::new();
+        while let Some(ch) = self.peek() {
+            if ch == '"'
+                && self.src[self.i + 1..]
+                    .chars()
+                    .take_while(|c| *c == '#')
+                    .count()
+                    >= hashes
+            {
+                self.bump(); // consume closing quote
+                for _ in 0..hashes {
+                    self.bump();
+                }
+                return Token {
+                    kind: TokenKind::StrLit(s),
+                    span: self.mk_span(sl, sc, self.line, self.col),
+                };
+            }
+            s.push(self.bump().unwrap());
+        }
+
+        let sp = self.mk_span(sl, sc, self.line, self.col);
+        self.err(
+            "lex-unterminated-string",
+            format!(
+                "Unterminated raw string literal (missing closing `\"{}`).",
+                "#".repeat(hashes)
+            ),
+            sp.clone(),
+        );
+        Token {
+            kind: TokenKind::StrLit(s),
+            span: sp,
+        }
+    }
+
+    /// At `r"` or `r#...#"`.
+    fn at_raw_string(&self) -> bool {
+        let rest = &self.src[self.i..];
+        let Some(after_r) = rest.strip_prefix('r') else {
+            return false;
+        };
+        after_r.trim_start_matches('#').starts_with('"')
+    }
+
+    /// One- or two-char operator: `double` if the next char is `second`, else `single`.
+    fn lex_op_pair(
+        &mut self,
+        sl: u32,
+        sc: u32,
+        second: char,
+        double: TokenKind,
+        single: TokenKind,
+    ) -> Token {
+        self.bump();
+        let kind = if self.peek() == Some(second) {
+            self.bump();
+            double
+        } else {
+            single
+        };
+        Token {
+            kind,
             span: self.mk_span(sl, sc, self.line, self.col),
         }
     }
 
     pub fn next_token(&mut self) -> Token {
+        loop {
+            if let Some(t) = self.lex_token() {
+                return t;
+            }
+        }
+    }
+
+    /// Lexes one token; `None` means an invalid character was reported and skipped.
+    fn lex_token(&mut self) -> Option<Token> {
         self.skip_ws();
 
         let sl = self.line;
         let sc = self.col;
 
         let Some(ch) = self.peek() else {
-            return Token {
+            return Some(Token {
                 kind: TokenKind::Eof,
                 span: self.mk_span(sl, sc, sl, sc),
-            };
+            });
         };
 
         // Single-char symbols
         match ch {
             '(' => {
                 self.bump();
-                return Token {
+                return Some(Token {
                     kind: TokenKind::LParen,
                     span: self.mk_span(sl, sc, self.line, self.col),
-                };
+                });
             }
             ')' => {
                 self.bump();
-                return Token {
+                return Some(Token {
                     kind: TokenKind::RParen,
                     span: self.mk_span(sl, sc, self.line, self.col),
-                };
+                });
             }
             '{' => {
                 self.bump();
-                return Token {
+                return Some(Token {
                     kind: TokenKind::LBrace,
                     span: self.mk_span(sl, sc, self.line, self.col),
-                };
+                });
             }
             '}' => {
                 self.bump();
-                return Token {
+                return Some(Token {
                     kind: TokenKind::RBrace,
                     span: self.mk_span(sl, sc, self.line, self.col),
-                };
+                });
             }
-            ':' => {
+            '[' => {
                 self.bump();
-                return Token {
-                    kind: TokenKind::Colon,
+                return Some(Token {
+                    kind: TokenKind::LBracket,
                     span: self.mk_span(sl, sc, self.line, self.col),
-                };
+                });
+            }
+            ']' => {
+                self.bump();
+                return Some(Token {
+                    kind: TokenKind::RBracket,
+                    span: self.mk_span(sl, sc, self.line, self.col),
+                });
+            }
+            '#' => {
+                self.bump();
+                return Some(Token {
+                    kind: TokenKind::Hash,
+                    span: self.mk_span(sl, sc, self.line, self.col),
+                });
+            }
+
+            ':' => {
+                return Some(self.lex_op_pair(
+                    sl,
+                    sc,
+                    ':',
+                    TokenKind::ColonColon,
+                    TokenKind::Colon,
+                ));
             }
             ';' => {
                 self.bump();
-                return Token {
+                return Some(Token {
                     kind: TokenKind::Semi,
                     span: self.mk_span(sl, sc, self.line, self.col),
-                };
+                });
             }
             ',' => {
                 self.bump();
-                return Token {
+                return Some(Token {
                     kind: TokenKind::Comma,
                     span: self.mk_span(sl, sc, self.line, self.col),
-                };
+                });
+            }
+            '=' if self.peek_at(1) == Some('>') => {
+                self.bump();
+                self.bump();
+                return Some(Token {
+                    kind: TokenKind::FatArrow,
+                    span: self.mk_span(sl, sc, self.line, self.col),
+                });
             }
             '=' => {
+                return Some(self.lex_op_pair(sl, sc, '=', TokenKind::EqEq, TokenKind::Eq));
+            }
+            '!' => {
+                return Some(self.lex_op_pair(sl, sc, '=', TokenKind::NotEq, TokenKind::Bang));
+            }
+            '<' => {
+                return Some(self.lex_op_pair(sl, sc, '=', TokenKind::Le, TokenKind::Lt));
+            }
+            '>' => {
+                return Some(self.lex_op_pair(sl, sc, '=', TokenKind::Ge, TokenKind::Gt));
+            }
+            '&' | '|' if self.peek_at(1) == Some(ch) => {
                 self.bump();
-                return Token {
-                    kind: TokenKind::Eq,
+                self.bump();
+                let kind = if ch == '&' {
+                    TokenKind::AndAnd
+                } else {
+                    TokenKind::OrOr
+                };
+                return Some(Token {
+                    kind,
                     span: self.mk_span(sl, sc, self.line, self.col),
+                });
+            }
+            '/' if self.at_doc_comment() => {
+                return Some(self.lex_doc_comment(sl, sc));
+            }
+            '+' | '*' | '/' | '%' => {
+                self.bump();
+                let kind = match ch {
+                    '+' => TokenKind::Plus,
+                    '*' => TokenKind::Star,
+                    '/' => TokenKind::Slash,
+                    _ => TokenKind::Percent,
                 };
+                return Some(Token {
+                    kind,
+                    span: self.mk_span(sl, sc, self.line, self.col),
+                });
             }
             '"' => {
-                return self.lex_string(sl, sc);
+                return Some(self.lex_string(sl, sc));
+            }
+            'r' if self.at_raw_string() => {
+                return Some(self.lex_raw_string(sl, sc));
+            }
+            '.' => {
+                return Some(self.lex_op_pair(sl, sc, '.', TokenKind::DotDot, TokenKind::Dot));
             }
             '-' => {
-                self.bump();
-                if self.peek() == Some('>') {
-                    self.bump();
-                    return Token {
-                        kind: TokenKind::Arrow,
-                        span: self.mk_span(sl, sc, self.line, self.col),
-                    };
-                }
-                return Token {
-                    kind: TokenKind::Ident("-".into()),
-                    span: self.mk_span(sl, sc, self.line, self.col),
-                };
+                return Some(self.lex_op_pair(sl, sc, '>', TokenKind::Arrow, TokenKind::Minus));
             }
             _ => {}
         }
 
-        // int literal
+        // int literal: decimal, or hex with `0x` (byte-friendly: `0xff`)
         if ch.is_ascii_digit() {
+            let hex = ch == '0' && matches!(self.peek_at(1), Some('x' | 'X'));
             let mut s = String::new();
-            while matches!(self.peek(), Some(c) if c.is_ascii_digit()) {
+            if hex {
+                self.bump();
+                self.bump();
+            }
+            while matches!(self.peek(), Some(c) if c.is_ascii_hexdigit() && (hex || c.is_ascii_digit()))
+            {
                 s.push(self.bump().unwrap());
             }
-            let v = s.parse::<i64>().unwrap_or(0);
-            return Token {
-                kind: TokenKind::IntLit(v),
-                span: self.mk_span(sl, sc, self.line, self.col),
+            let sp = self.mk_span(sl, sc, self.line, self.col);
+            let v = match i64::from_str_radix(&s, if hex { 16 } else { 10 }) {
+                Ok(v) => v,
+                Err(_) if s.is_empty() => {
+                    self.err(
+                        "lex-invalid-int",
+                        "Hex literal `0x` needs at least one digit.",
+                        sp.clone(),
+                    );
+                    0
+                }
+                Err(_) => {
+                    let text = if hex { format!("0x{s}") } else { s };
+                    self.err(
+                        "lex-int-overflow",
+                        format!("Integer literal `{text}` does not fit in Int (64-bit signed)."),
+                        sp.clone(),
+                    );
+                    0
+                }
             };
+            return Some(Token {
+                kind: TokenKind::IntLit(v),
+                span: sp,
+            });
         }
 
         // identifier / keyword
@@ -248,11 +648,21 @@ impl<'a> Lexer<'a> {
             let kind = match s.as_str() {
                 "fn" => TokenKind::KwFn,
                 "let" => TokenKind::KwLet,
+                "mut" => TokenKind::KwMut,
                 "return" => TokenKind::KwReturn,
                 "secret" => TokenKind::KwSecret,
                 "if" => TokenKind::KwIf,
                 "else" => TokenKind::KwElse,
+                "for" => TokenKind::KwFor,
+                "in" => TokenKind::KwIn,
                 "effects" => TokenKind::KwEffects,
+                "import" => TokenKind::KwImport,
+                "mod" => TokenKind::KwMod,
+                "struct" => TokenKind::KwStruct,
+                "enum" => TokenKind::KwEnum,
+                "match" => TokenKind::KwMatch,
+                "type" => TokenKind::KwType,
+                "const" => TokenKind::KwConst,
                 "protocol" => TokenKind::ProtocolKw,
                 "state" => TokenKind::StateKw,
                 "transition" => TokenKind::TransitionKw,
@@ -260,17 +670,20 @@ impl<'a> Lexer<'a> {
                 _ => TokenKind::Ident(s),
             };
 
-            return Token {
+            return Some(Token {
                 kind,
                 span: self.mk_span(sl, sc, self.line, self.col),
-            };
+            });
         }
 
-        // unknown: consume 1 char, return as Ident to avoid lexer failure
+        // unknown: report, consume 1 char and keep lexing
         self.bump();
-        Token {
-            kind: TokenKind::Ident(ch.to_string()),
-            span: self.mk_span(sl, sc, self.line, self.col),
-        }
+        let sp = self.mk_span(sl, sc, self.line, self.col);
+        self.err(
+            "lex-unknown-char",
+            format!("Unknown character `{}`.", ch.escape_default()),
+            sp,
+        );
+        None
     }
 }
@@ -0,0 +1,61 @@
+use candy_lexer::{Lexer, TokenKind};
+
+fn kinds(src: &str) -> Vec<TokenKind> {
+    Lexer::new("<t>", src)
+        .lex_all()
+        .into_iter()
+        .map(|t| t.kind)
+        .collect()
+}
+
+#[test]
+fn line_comments_are_skipped() {
+    let k = kinds("let x = 1; // trailing\n// full line\nreturn;");
+    assert_eq!(
+        k,
+        vec![
+            TokenKind::KwLet,
+            TokenKind::Ident("x".into()),
+            TokenKind::Eq,
+            TokenKind::IntLit(1),
+            TokenKind::Semi,
+            TokenKind::KwReturn,
+            TokenKind::Semi,
+            TokenKind::Eof,
+        ]
+    );
+}
+
+#[test]
+fn nested_block_comments_are_skipped() {
+    let k = kinds("fn /* outer /* inner */ still outer */ main");
+    assert_eq!(
+        k,
+        vec![
+            TokenKind::KwFn,
+            TokenKind::Ident("main".into()),
+            TokenKind::Eof
+        ]
+    );
+}
+
+#[test]
+fn block_comment_keeps_positions_accurate() {
+    let toks = Lexer::new("<t>", "/* a\n   b */ fn").lex_all();
+    assert!(matches!(toks[0].kind, TokenKind::KwFn));
+    assert_eq!(toks[0].span.start_line, 2);
+    assert_eq!(toks[0].span.start_col, 9);
+}
+
+#[test]
+fn doc_comments_are_tokens() {
+    let k = kinds("/// Opens the channel.\n//// not a doc\nfn");
+    assert_eq!(
+        k,
+        vec![
+            TokenKind::DocComment("Opens the channel.".into()),
+            TokenKind::KwFn,
+            TokenKind::Eof
+        ]
+    );
+}
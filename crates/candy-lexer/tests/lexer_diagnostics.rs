@@ -0,0 +1,62 @@
+use candy_lexer::{Lexer, TokenKind};
+
+/// (code, line, start col, end col)
+type DiagPos = (String, u32, u32, u32);
+
+fn lex(src: &str) -> (Vec<TokenKind>, Vec<DiagPos>) {
+    let (toks, report) = Lexer::new("main.candy", src).lex_all_with_report();
+    let kinds = toks.into_iter().map(|t| t.kind).collect();
+    let diags = report
+        .diagnostics
+        .into_iter()
+        .map(|d| (d.code, d.span.start_line, d.span.start_col, d.span.end_col))
+        .collect();
+    (kinds, diags)
+}
+
+#[test]
+fn unterminated_string_is_reported() {
+    let (kinds, diags) = lex("log(\"hello\n");
+    assert_eq!(diags, vec![("lex-unterminated-string".into(), 1, 5, 11)]);
+    assert!(kinds.contains(&TokenKind::StrLit("hello".into())));
+}
+
+#[test]
+fn unknown_char_is_reported_and_skipped() {
+    let (kinds, diags) = lex("let x = 1 @ 2;");
+    assert_eq!(diags, vec![("lex-unknown-char".into(), 1, 11, 12)]);
+    assert!(!kinds
+        .iter()
+        .any(|k| matches!(k, TokenKind::Ident(s) if s == "@")));
+    assert!(kinds.contains(&TokenKind::IntLit(2)));
+}
+
+#[test]
+fn int_overflow_is_reported() {
+    let (_, diags) = lex("return 99999999999999999999;");
+    assert_eq!(diags, vec![("lex-int-overflow".into(), 1, 8, 28)]);
+
+    let (kinds, diags) = lex("return 9223372036854775807;");
+    assert!(diags.is_empty());
+    assert!(kinds.contains(&TokenKind::IntLit(i64::MAX)));
+}
+
+#[test]
+fn backslash_in_string_is_invalid_escape() {
+    let (_, diags) = lex(r#""a\qb""#);
+    assert_eq!(diags, vec![("lex-invalid-escape".into(), 1, 3, 5)]);
+}
+
+#[test]
+fn unterminated_block_comment_is_reported() {
+    let (kinds, diags) = lex("fn /* never closed");
+    assert_eq!(diags[0].0, "lex-unterminated-comment");
+    assert_eq!(kinds, vec![TokenKind::KwFn, TokenKind::Eof]);
+}
+
+#[test]
+fn empty_hex_literal_is_reported() {
+    let (kinds, diags) = lex("0x;");
+    assert_eq!(diags, vec![("lex-invalid-int".into(), 1, 1, 3)]);
+    assert_eq!(kinds[0], TokenKind::IntLit(0));
+}
@@ -0,0 +1,105 @@
+use candy_lexer::{Lexer, TokenKind};
+
+#[test]
+fn lex_all_operators() {
+    let src = "+ - * / % == != < <= > >= && || ! = -> => . ..";
+    let kinds: Vec<TokenKind> = Lexer::new("<t>", src)
+        .lex_all()
+        .into_iter()
+        .map(|t| t.kind)
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::Plus,
+            TokenKind::Minus,
+            TokenKind::Star,
+            TokenKind::Slash,
+            TokenKind::Percent,
+            TokenKind::EqEq,
+            TokenKind::NotEq,
+            TokenKind::Lt,
+            TokenKind::Le,
+            TokenKind::Gt,
+            TokenKind::Ge,
+            TokenKind::AndAnd,
+            TokenKind::OrOr,
+            TokenKind::Bang,
+            TokenKind::Eq,
+            TokenKind::Arrow,
+            TokenKind::FatArrow,
+            TokenKind::Dot,
+            TokenKind::DotDot,
+            TokenKind::Eof,
+        ]
+    );
+}
+
+#[test]
+fn two_char_operator_spans() {
+    let toks = Lexer::new("<t>", "a<=b").lex_all();
+    assert_eq!(toks[1].kind, TokenKind::Le);
+    assert_eq!((toks[1].span.start_col, toks[1].span.end_col), (2, 4));
+}
+
+#[test]
+fn single_ampersand_is_unknown_char() {
+    let (toks, report) = Lexer::new("<t>", "a & b").lex_all_with_report();
+    assert_eq!(report.diagnostics[0].code, "lex-unknown-char");
+    assert_eq!(toks.len(), 3); // a b EOF
+}
+
+#[test]
+fn range_and_path_tokens() {
+    let kinds: Vec<TokenKind> = Lexer::new("<t>", "0..N keys::derive")
+        .lex_all()
+        .into_iter()
+        .map(|t| t.kind)
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::IntLit(0),
+            TokenKind::DotDot,
+            TokenKind::Ident("N".into()),
+            TokenKind::Ident("keys".into()),
+            TokenKind::ColonColon,
+            TokenKind::Ident("derive".into()),
+            TokenKind::Eof,
+        ]
+    );
+}
+
+#[test]
+fn lex_brackets_and_hex_literals() {
+    let src = "[0x1f, 0XFF, 7]";
+    let kinds: Vec<TokenKind> = Lexer::new("<t>", src)
+        .lex_all()
+        .into_iter()
+        .map(|t| t.kind)
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::LBracket,
+            TokenKind::IntLit(31),
+            TokenKind::Comma,
+            TokenKind::IntLit(255),
+            TokenKind::Comma,
+            TokenKind::IntLit(7),
+            TokenKind::RBracket,
+            TokenKind::Eof,
+        ]
+    );
+}
+
+#[test]
+fn hash_starts_an_attribute() {
+    let kinds: Vec<TokenKind> = Lexer::new("<t>", "#[deny(x)]")
+        .lex_all()
+        .into_iter()
+        .map(|t| t.kind)
+        .collect();
+    assert_eq!(kinds[0], TokenKind::Hash);
+    assert_eq!(kinds[1], TokenKind::LBracket);
+}
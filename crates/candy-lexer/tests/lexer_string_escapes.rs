@@ -0,0 +1,69 @@
+use candy_lexer::{Lexer, TokenKind};
+
+fn single_str(src: &str) -> (String, Vec<String>) {
+    let (toks, report) = Lexer::new("<t>", src).lex_all_with_report();
+    let s = match &toks[0].kind {
+        TokenKind::StrLit(s) => s.clone(),
+        k => panic!("expected StrLit, got {k:?}"),
+    };
+    (s, report.diagnostics.into_iter().map(|d| d.code).collect())
+}
+
+#[test]
+fn simple_escapes_are_decoded() {
+    let (s, diags) = single_str(r#""a\n\t\\\"b""#);
+    assert_eq!(s, "a\n\t\\\"b");
+    assert!(diags.is_empty());
+}
+
+#[test]
+fn unicode_escape_is_decoded() {
+    let (s, diags) = single_str(r#""\u{1F36D} \u{41}""#);
+    assert_eq!(s, "\u{1F36D} A");
+    assert!(diags.is_empty());
+}
+
+#[test]
+fn bad_unicode_escapes_are_reported() {
+    for src in [
+        r#""\u{}""#,
+        r#""\u{110000}""#,
+        r#""\u{D800}""#,
+        r#""\u41""#,
+        r#""\u{1234567}""#,
+    ] {
+        let (_, diags) = single_str(src);
+        assert_eq!(diags, vec!["lex-invalid-escape"], "src={src}");
+    }
+}
+
+#[test]
+fn raw_strings_keep_backslashes_and_quotes() {
+    let (s, diags) = single_str(r##"r"C:\keys\n""##);
+    assert_eq!(s, r"C:\keys\n");
+    assert!(diags.is_empty());
+
+    let (s, diags) = single_str(r###"r#"say "hi""#"###);
+    assert_eq!(s, r#"say "hi""#);
+    assert!(diags.is_empty());
+}
+
+#[test]
+fn raw_string_may_span_lines() {
+    let toks = Lexer::new("<t>", "r\"a\nb\" x").lex_all();
+    assert_eq!(toks[0].kind, TokenKind::StrLit("a\nb".into()));
+    assert_eq!(toks[1].span.start_line, 2);
+}
+
+#[test]
+fn unterminated_raw_string_is_reported() {
+    let (s, diags) = single_str(r##"r#"open" "##);
+    assert_eq!(s, "open\" ");
+    assert_eq!(diags, vec!["lex-unterminated-string"]);
+}
+
+#[test]
+fn identifier_starting_with_r_is_not_raw_string() {
+    let toks = Lexer::new("<t>", "rand()").lex_all();
+    assert_eq!(toks[0].kind, TokenKind::Ident("rand".into()));
+}
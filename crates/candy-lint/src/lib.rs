@@ -0,0 +1,467 @@
+// Lint rules
+//
+// Style checks that, unlike typecheck errors, teams may relax or tighten. Every rule has
+// a default level; `#[allow(rule)]`, `#[warn(rule)]` and `#[deny(rule)]` on a function
+// change it for that function. The rule id is also the diagnostic code.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use candy_ast::{Block, Expr, FnDecl, Ident, LintLevel, Pattern, Program, Stmt};
+use candy_diagnostics::{Diagnostic, DiagnosticReport, Span};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    pub id: &'static str,
+    pub default: LintLevel,
+    pub description: &'static str,
+}
+
+/// Every lint rule.
+pub const RULES: &[Rule] = &[
+    Rule {
+        id: "unused-variable",
+        default: LintLevel::Warn,
+        description: "A `let`, loop or pattern binding is never read.",
+    },
+    Rule {
+        id: "unused-parameter",
+        default: LintLevel::Warn,
+        description: "A function parameter is never read.",
+    },
+    Rule {
+        id: "unused-function",
+        default: LintLevel::Warn,
+        description: "A function is not reachable from `main`.",
+    },
+    Rule {
+        id: "duplicate-effect",
+        default: LintLevel::Warn,
+        description: "An effect is listed more than once in one `effects(...)` clause.",
+    },
+];
+
+pub fn rule(id: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|r| r.id == id)
+}
+
+/// Runs every rule over `p`. Names starting with `_` are exempt from the unused rules.
+pub fn lint(p: &Program) -> DiagnosticReport {
+    let mut r = DiagnosticReport::new();
+    let levels: Vec<Levels> = p.funcs.iter().map(|f| levels_of(f, &mut r)).collect();
+
+    for (f, lv) in p.funcs.iter().zip(&levels) {
+        let mut found = Vec::new();
+        check_duplicate_effects(f, lv, &mut found);
+        check_unused_bindings(f, lv, &mut found);
+        found.sort_by_key(|d| (d.span.start_line, d.span.start_col));
+        r.diagnostics.append(&mut found);
+    }
+    check_unused_functions(p, &levels, &mut r);
+    r
+}
+
+/// Rule levels inside one function: the defaults, overridden by its attributes in order.
+struct Levels(HashMap<&'static str, LintLevel>);
+
+impl Levels {
+    /// Reports `message` under `rule` at its level here (nothing if allowed).
+    fn emit(&self, rule: &str, message: String, span: &Span) -> Option<Diagnostic> {
+        match self.0[rule] {
+            LintLevel::Allow => None,
+            LintLevel::Warn => Some(Diagnostic::warning(rule, message, span.clone())),
+            LintLevel::Deny => Some(Diagnostic::error(rule, message, span.clone())),
+        }
+    }
+}
+
+/// `lint-unknown-rule` (warning) for an attribute naming no rule.
+fn levels_of(f: &FnDecl, r: &mut DiagnosticReport) -> Levels {
+    let mut levels: HashMap<&'static str, LintLevel> =
+        RULES.iter().map(|r| (r.id, r.default)).collect();
+    for a in &f.attrs {
+        for id in &a.rules {
+            match rule(&id.name) {
+                Some(rule) => {
+                    levels.insert(rule.id, a.level);
+                }
+                None => r.push(Diagnostic::warning(
+                    "lint-unknown-rule",
+                    format!(
+                        "Unknown lint rule `{}` in `#[{}(...)]`; known rules: {}.",
+                        id.name,
+                        a.level.as_str(),
+                        RULES.iter().map(|r| r.id).collect::<Vec<_>>().join(", ")
+                    ),
+                    id.span.clone(),
+                )),
+            }
+        }
+    }
+    Levels(levels)
+}
+
+fn check_duplicate_effects(f: &FnDecl, lv: &Levels, out: &mut Vec<Diagnostic>) {
+    let written: Vec<&str> = f.effects.iter().map(|e| e.effect.as_str()).collect();
+    let mut seen = BTreeSet::new();
+    let mut unique = Vec::new();
+    for e in &f.effects {
+        if seen.insert(e.effect) {
+            unique.push(e.effect.as_str());
+            continue;
+        }
+        let msg = format!(
+            "Effect `{}` is listed more than once in the effects of `{}`.",
+            e.effect.as_str(),
+            f.name.name
+        );
+        out.extend(lv.emit("duplicate-effect", msg, &e.span).map(|d| {
+            d.with_fix(
+                format!("effects({})", written.join(", ")),
+                format!("effects({})", unique.join(", ")),
+            )
+        }));
+    }
+}
+
+/// A binding in scope; `used` is set by the first read.
+struct Binding<'a> {
+    name: &'a Ident,
+    kind: BindingKind,
+    used: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BindingKind {
+    Param,
+    Let {
+        mutable: bool,
+    },
+    /// A `for` variable or a `match` pattern binding.
+    Other,
+}
+
+/// Scopes of one function body, innermost last.
+struct Uses<'a> {
+    scopes: Vec<Vec<Binding<'a>>>,
+    lv: &'a Levels,
+    fn_name: &'a str,
+    out: &'a mut Vec<Diagnostic>,
+}
+
+fn check_unused_bindings(f: &FnDecl, lv: &Levels, out: &mut Vec<Diagnostic>) {
+    let mut u = Uses {
+        scopes: Vec::new(),
+        lv,
+        fn_name: &f.name.name,
+        out,
+    };
+    u.enter();
+    for p in &f.params {
+        u.bind(&p.name, BindingKind::Param);
+    }
+    u.block(&f.body);
+    u.leave();
+}
+
+impl<'a> Uses<'a> {
+    fn enter(&mut self) {
+        self.scopes.push(Vec::new());
+    }
+
+    /// Ends the innermost scope and reports its bindings that were never read.
+    fn leave(&mut self) {
+        let scope = self.scopes.pop().unwrap_or_default();
+        for b in scope {
+            if b.used || b.name.name.starts_with('_') {
+                continue;
+            }
+            let n = &b.name.name;
+            let d = match b.kind {
+                BindingKind::Param => self
+                    .lv
+                    .emit(
+                        "unused-parameter",
+                        format!("Parameter `{}` of `{}` is never read.", n, self.fn_name),
+                        &b.name.span,
+                    )
+                    .map(|d| d.with_fix(format!("{}:", n), format!("_{}:", n))),
+                BindingKind::Let { mutable } => {
+                    let kw = if mutable { "let mut" } else { "let" };
+                    self.lv
+                        .emit(
+                            "unused-variable",
+                            format!("Variable `{}` is never read.", n),
+                            &b.name.span,
+                        )
+                        .map(|d| d.with_fix(format!("{} {}", kw, n), format!("{} _{}", kw, n)))
+                }
+                BindingKind::Other => self.lv.emit(
+                    "unused-variable",
+                    format!("Variable `{}` is never read.", n),
+                    &b.name.span,
+                ),
+            };
+            self.out.extend(d);
+        }
+    }
+
+    fn bind(&mut self, name: &'a Ident, kind: BindingKind) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(Binding {
+                name,
+                kind,
+                used: false,
+            });
+        }
+    }
+
+    /// Marks the innermost binding of `name` as read (constants have none).
+    fn read(&mut self, name: &str) {
+        if let Some(b) = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .flat_map(|s| s.iter_mut().rev())
+            .find(|b| b.name.name == name)
+        {
+            b.used = true;
+        }
+    }
+
+    fn block(&mut self, b: &'a Block) {
+        self.enter();
+        for s in &b.stmts {
+            self.stmt(s);
+        }
+        self.leave();
+    }
+
+    fn stmt(&mut self, s: &'a Stmt) {
+        match s {
+            Stmt::Let {
+                name,
+                mutable,
+                expr,
+                ..
+            } => {
+                self.expr(expr);
+                self.bind(name, BindingKind::Let { mutable: *mutable });
+            }
+            // Assigning is not reading.
+            Stmt::Assign { expr, .. } => self.expr(expr),
+            Stmt::Return { expr, .. } => {
+                if let Some(e) = expr {
+                    self.expr(e);
+                }
+            }
+            Stmt::If {
+                cond,
+                then_blk,
+                else_blk,
+                ..
+            } => {
+                self.expr(cond);
+                self.block(then_blk);
+                if let Some(b) = else_blk {
+                    self.block(b);
+                }
+            }
+            Stmt::For {
+                var,
+                start,
+                end,
+                body,
+                ..
+            } => {
+                self.expr(start);
+                self.expr(end);
+                self.enter();
+                self.bind(var, BindingKind::Other);
+                self.block(body);
+                self.leave();
+            }
+            Stmt::Match {
+                scrutinee, arms, ..
+            } => {
+                self.expr(scrutinee);
+                for arm in arms {
+                    self.enter();
+                    if let Pattern::Variant { bindings, .. } = &arm.pattern {
+                        for b in bindings.iter().filter(|b| b.name != "_") {
+                            self.bind(b, BindingKind::Other);
+                        }
+                    }
+                    self.block(&arm.body);
+                    self.leave();
+                }
+            }
+            Stmt::Expr { expr, .. } => self.expr(expr),
+            Stmt::Error { .. } => {}
+        }
+    }
+
+    fn expr(&mut self, e: &'a Expr) {
+        match e {
+            Expr::Var { name, .. } | Expr::Move { name, .. } => self.read(&name.name),
+            Expr::Call { args, .. } | Expr::ArrayLit { elems: args, .. } => {
+                for a in args {
+                    self.expr(a);
+                }
+            }
+            Expr::Field { base, .. } => self.expr(base),
+            Expr::Index { base, index, .. } => {
+                self.expr(base);
+                self.expr(index);
+            }
+            Expr::Slice {
+                base, start, end, ..
+            } => {
+                self.expr(base);
+                self.expr(start);
+                self.expr(end);
+            }
+            Expr::ArrayRepeat { elem, count, .. } => {
+                self.expr(elem);
+                self.expr(count);
+            }
+            Expr::StructLit { fields, .. } => {
+                for f in fields {
+                    self.expr(&f.expr);
+                }
+            }
+            Expr::Unary { expr, .. } => self.expr(expr),
+            Expr::Binary { lhs, rhs, .. } => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            Expr::IntLit { .. }
+            | Expr::BoolLit { .. }
+            | Expr::StrLit { .. }
+            | Expr::Error { .. } => {}
+        }
+    }
+}
+
+/// Functions not reachable from `main` through calls. Programs without `main` (libraries)
+/// are not checked.
+fn check_unused_functions(p: &Program, levels: &[Levels], r: &mut DiagnosticReport) {
+    if !p.funcs.iter().any(|f| f.name.name == "main") {
+        return;
+    }
+    let calls: HashMap<&str, Vec<&str>> = p
+        .funcs
+        .iter()
+        .map(|f| {
+            let mut out = Vec::new();
+            calls_in_block(&f.body, &mut out);
+            (f.name.name.as_str(), out)
+        })
+        .collect();
+
+    let mut reached: HashSet<&str> = HashSet::new();
+    let mut todo = vec!["main"];
+    while let Some(n) = todo.pop() {
+        if reached.insert(n) {
+            todo.extend(calls.get(n).into_iter().flatten().copied());
+        }
+    }
+
+    for (f, lv) in p.funcs.iter().zip(levels) {
+        if reached.contains(f.name.name.as_str()) || f.name.name.starts_with('_') {
+            continue;
+        }
+        let msg = format!("Function `{}` is never called from `main`.", f.name.name);
+        r.diagnostics
+            .extend(lv.emit("unused-function", msg, &f.name.span));
+    }
+}
+
+fn calls_in_block<'a>(b: &'a Block, out: &mut Vec<&'a str>) {
+    for s in &b.stmts {
+        match s {
+            Stmt::Let { expr, .. } | Stmt::Assign { expr, .. } | Stmt::Expr { expr, .. } => {
+                calls_in_expr(expr, out)
+            }
+            Stmt::Return { expr, .. } => {
+                if let Some(e) = expr {
+                    calls_in_expr(e, out);
+                }
+            }
+            Stmt::If {
+                cond,
+                then_blk,
+                else_blk,
+                ..
+            } => {
+                calls_in_expr(cond, out);
+                calls_in_block(then_blk, out);
+                if let Some(eb) = else_blk {
+                    calls_in_block(eb, out);
+                }
+            }
+            Stmt::For {
+                start, end, body, ..
+            } => {
+                calls_in_expr(start, out);
+                calls_in_expr(end, out);
+                calls_in_block(body, out);
+            }
+            Stmt::Match {
+                scrutinee, arms, ..
+            } => {
+                calls_in_expr(scrutinee, out);
+                for arm in arms {
+                    calls_in_block(&arm.body, out);
+                }
+            }
+            Stmt::Error { .. } => {}
+        }
+    }
+}
+
+fn calls_in_expr<'a>(e: &'a Expr, out: &mut Vec<&'a str>) {
+    match e {
+        Expr::Call { callee, args, .. } => {
+            out.push(&callee.name);
+            for a in args {
+                calls_in_expr(a, out);
+            }
+        }
+        Expr::ArrayLit { elems, .. } => {
+            for el in elems {
+                calls_in_expr(el, out);
+            }
+        }
+        Expr::Field { base, .. } | Expr::Unary { expr: base, .. } => calls_in_expr(base, out),
+        Expr::Index { base, index, .. } => {
+            calls_in_expr(base, out);
+            calls_in_expr(index, out);
+        }
+        Expr::Slice {
+            base, start, end, ..
+        } => {
+            calls_in_expr(base, out);
+            calls_in_expr(start, out);
+            calls_in_expr(end, out);
+        }
+        Expr::ArrayRepeat { elem, count, .. } => {
+            calls_in_expr(elem, out);
+            calls_in_expr(count, out);
+        }
+        Expr::StructLit { fields, .. } => {
+            for f in fields {
+                calls_in_expr(&f.expr, out);
+            }
+        }
+        Expr::Binary { lhs, rhs, .. } => {
+            calls_in_expr(lhs, out);
+            calls_in_expr(rhs, out);
+        }
+        Expr::IntLit { .. }
+        | Expr::BoolLit { .. }
+        | Expr::StrLit { .. }
+        | Expr::Var { .. }
+        | Expr::Move { .. }
+        | Expr::Error { .. } => {}
+    }
+}
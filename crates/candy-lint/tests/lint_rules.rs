@@ -0,0 +1,186 @@
+use candy_ast::LintLevel;
+use candy_diagnostics::Severity;
+use candy_lint::{lint, rule, RULES};
+use candy_parser::parse_program;
+
+fn diags(src: &str) -> Vec<(String, Severity, String)> {
+    let p = parse_program(src).expect("parse ok");
+    lint(&p)
+        .diagnostics
+        .into_iter()
+        .map(|d| (d.code, d.severity, d.message))
+        .collect()
+}
+
+fn codes(src: &str) -> Vec<String> {
+    diags(src).into_iter().map(|(c, _, _)| c).collect()
+}
+
+#[test]
+fn registry_lists_every_rule_with_a_default() {
+    let ids: Vec<&str> = RULES.iter().map(|r| r.id).collect();
+    assert_eq!(
+        ids,
+        vec![
+            "unused-variable",
+            "unused-parameter",
+            "unused-function",
+            "duplicate-effect"
+        ]
+    );
+    assert_eq!(rule("unused-function").unwrap().default, LintLevel::Warn);
+    assert!(rule("unused-import").is_none());
+}
+
+#[test]
+fn unused_variable_and_parameter_warn_by_default() {
+    let src = r#"
+fn f(a: Int, b: Int) -> Int {
+  let x: Int = a;
+  let y: Int = 2;
+  return x;
+}
+fn main() -> Unit { let n: Int = f(1, 2); log("n"); return; }
+"#;
+    let d = diags(src);
+    assert_eq!(d.len(), 3, "{d:?}");
+    assert_eq!(d[0].0, "unused-parameter");
+    assert_eq!(d[0].1, Severity::Warning);
+    assert_eq!(d[0].2, "Parameter `b` of `f` is never read.");
+    assert_eq!(d[1].2, "Variable `y` is never read.");
+    assert_eq!(d[2].2, "Variable `n` is never read.");
+}
+
+#[test]
+fn underscore_names_and_shadowed_reads_are_handled() {
+    let src = r#"
+fn main() -> Unit {
+  let _skip: Int = 1;
+  let x: Int = 1;
+  let x: Int = x + 1;
+  let c: Bool = true;
+  if (c) { let y: Int = x; }
+  return;
+}
+"#;
+    // Each `x` is read (by the next `let`), so only `y` is reported.
+    let d = diags(src);
+    assert_eq!(d.len(), 1, "{d:?}");
+    assert!(d[0].2.contains("`y`"), "{}", d[0].2);
+}
+
+#[test]
+fn loop_and_pattern_bindings_count() {
+    let src = r#"
+enum E { A(Int), B }
+fn main() -> Unit {
+  for i in 0..4 { log("tick"); }
+  let e: E = E::A(1);
+  match e {
+    E::A(n) => { }
+    E::B => { }
+  }
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["unused-variable", "unused-variable"]);
+}
+
+#[test]
+fn assignment_is_not_a_read() {
+    let src = r#"
+fn main() -> Unit {
+  let mut x: Int = 1;
+  x = 2;
+  return;
+}
+"#;
+    let p = parse_program(src).unwrap();
+    let r = lint(&p);
+    assert_eq!(r.diagnostics.len(), 1);
+    let fix = r.diagnostics[0].fix.as_ref().unwrap();
+    assert_eq!(
+        (fix.replace.as_str(), fix.with.as_str()),
+        ("let mut x", "let mut _x")
+    );
+}
+
+#[test]
+fn functions_unreachable_from_main_are_unused() {
+    let src = r#"
+fn helper() -> Int { return 1; }
+fn used() -> Int { return helper(); }
+fn dead() -> Int { return dead_too(); }
+fn dead_too() -> Int { return dead(); }
+fn main() -> Unit { let n: Int = used(); log("x"); return; }
+"#;
+    let d: Vec<String> = diags(src)
+        .into_iter()
+        .filter(|(c, _, _)| c == "unused-function")
+        .map(|(_, _, m)| m)
+        .collect();
+    assert_eq!(
+        d,
+        vec![
+            "Function `dead` is never called from `main`.",
+            "Function `dead_too` is never called from `main`."
+        ]
+    );
+}
+
+#[test]
+fn programs_without_main_have_no_unused_functions() {
+    assert_eq!(codes("fn f() -> Int { return 1; }"), Vec::<String>::new());
+}
+
+#[test]
+fn duplicate_effect_has_a_fix() {
+    let src = r#"
+fn main() -> Unit effects(io, time, io) { log("x"); let t: Int = now(); log("t"); return; }
+"#;
+    let p = parse_program(src).unwrap();
+    let r = lint(&p);
+    let d: Vec<_> = r
+        .diagnostics
+        .iter()
+        .filter(|d| d.code == "duplicate-effect")
+        .collect();
+    assert_eq!(d.len(), 1);
+    assert_eq!(d[0].span.start_col, 37);
+    let fix = d[0].fix.as_ref().unwrap();
+    assert_eq!(fix.replace, "effects(io, time, io)");
+    assert_eq!(fix.with, "effects(io, time)");
+}
+
+#[test]
+fn attributes_relax_and_tighten_rules() {
+    let src = r#"
+#[allow(unused-variable, unused-parameter)]
+fn quiet(a: Int) -> Unit { let x: Int = 1; return; }
+
+#[deny(unused-variable)]
+#[allow(unused-function)]
+fn strict() -> Unit { let y: Int = 1; return; }
+
+fn main() -> Unit { quiet(1); return; }
+"#;
+    let d = diags(src);
+    assert_eq!(d.len(), 1, "{d:?}");
+    assert_eq!(d[0].0, "unused-variable");
+    assert_eq!(d[0].1, Severity::Error);
+    assert!(d[0].2.contains("`y`"));
+}
+
+#[test]
+fn later_attribute_wins_and_unknown_rules_warn() {
+    let src = r#"
+#[deny(unused-variable)]
+#[warn(unused-variable, unused-vars)]
+fn main() -> Unit { let y: Int = 1; return; }
+"#;
+    let d = diags(src);
+    assert_eq!(d[0].0, "lint-unknown-rule");
+    assert!(d[0].2.contains("`unused-vars`"), "{}", d[0].2);
+    assert_eq!(d[1].0, "unused-variable");
+    assert_eq!(d[1].1, Severity::Warning);
+}
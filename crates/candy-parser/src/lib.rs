@@ -1,10 +1,16 @@
 use candy_ast::{
-    Block, Effect, EffectSpec, Expr, FnDecl, Ident, Param, Program, ProtocolDecl, StateDecl, Stmt,
-    TransitionDecl, Type,
+    Attribute, BinOp, Block, ConstDecl, Effect, EffectSpec, EnumDecl, Expr, FieldDecl, FieldInit,
+    FnDecl, GenericParam, Ident, ImportDecl, LabelDecl, LintLevel, MatchArm, Param, Pattern,
+    Program, ProtocolDecl, StateDecl, Stmt, StructDecl, TransitionDecl, Type, TypeAlias, UnOp,
+    VariantDecl,
 };
 use candy_diagnostics::{Diagnostic, DiagnosticReport, Span};
 use candy_lexer::{Lexer, Token, TokenKind};
 
+mod loader;
+
+pub use loader::parse_file_with_imports;
+
 /// v0.1 compatibility: parse from memory buffer.
 pub fn parse_program(src: &str) -> Result<Program, DiagnosticReport> {
     parse_file("<memory>", src)
@@ -12,36 +18,88 @@ pub fn parse_program(src: &str) -> Result<Program, DiagnosticReport> {
 
 /// v0.2 canonical entrypoint: parse a file with spans.
 pub fn parse_file(file: &str, src: &str) -> Result<Program, DiagnosticReport> {
-    let mut p = Parser::new(file, src);
-    let prog = p.parse_program();
-    if p.report.is_ok() {
+    let (prog, report) = parse_file_with_report(file, src);
+    if report.is_ok() {
         Ok(prog)
     } else {
-        Err(p.report)
+        Err(report)
     }
 }
 
+/// Error-tolerant entrypoint: always returns a (possibly partial) program together with
+/// the lexical + parse diagnostics. Broken statements/expressions become `Stmt::Error` /
+/// `Expr::Error` nodes, so the typechecker can still run on the well-formed parts.
+pub fn parse_file_with_report(file: &str, src: &str) -> (Program, DiagnosticReport) {
+    let mut p = Parser::new(file, src);
+    let prog = p.parse_program();
+
+    // Lexical errors come first: they usually explain the parse errors that follow.
+    let mut report = p.lx.take_report();
+    report.diagnostics.append(&mut p.report.diagnostics);
+
+    (prog, report)
+}
+
+/// Name given to identifiers that failed to parse (already reported).
+const ERROR_NAME: &str = "_error_";
+
+/// Module name for an import path: the file stem of a `.candy` file, if it is an identifier.
+fn module_name_of(path: &str) -> Option<String> {
+    let file = path.rsplit(['/', '\\']).next()?;
+    let stem = file.strip_suffix(".candy")?;
+    let mut chars = stem.chars();
+    let first = chars.next()?;
+    let ok = (first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    ok.then(|| stem.to_string())
+}
+
 struct Parser<'a> {
     file: String,
     lx: Lexer<'a>,
     cur: Token,
+    /// Doc comments seen directly before `cur`.
+    docs: Vec<String>,
+    /// Set while parsing `for` bounds, where `N {` starts the body, not a struct literal.
+    no_struct_lit: bool,
     report: DiagnosticReport,
 }
 
 impl<'a> Parser<'a> {
     fn new(file: &str, src: &'a str) -> Self {
-        let mut lx = Lexer::new(file.to_string(), src);
-        let cur = lx.next_token();
-        Self {
+        let lx = Lexer::new(file.to_string(), src);
+        let mut p = Self {
             file: file.to_string(),
             lx,
-            cur,
+            cur: Token {
+                kind: TokenKind::Eof,
+                span: Span::unknown(file.to_string()),
+            },
+            docs: Vec::new(),
+            no_struct_lit: false,
             report: DiagnosticReport::new(),
-        }
+        };
+        p.bump();
+        p
     }
 
     fn bump(&mut self) {
-        self.cur = self.lx.next_token();
+        // Doc comments only attach to the token right after them.
+        self.docs.clear();
+        loop {
+            let t = self.lx.next_token();
+            match t.kind {
+                TokenKind::DocComment(text) => self.docs.push(text),
+                _ => {
+                    self.cur = t;
+                    break;
+                }
+            }
+        }
+    }
+
+    fn take_docs(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.docs)
     }
 
     fn err(&mut self, code: &str, msg: &str, span: Span) {
@@ -59,7 +117,71 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Tokens an error fallback must not consume: they are the anchors recovery resumes at.
+    fn at_sync_token(&self) -> bool {
+        matches!(
+            self.cur.kind,
+            TokenKind::Semi
+                | TokenKind::RBrace
+                | TokenKind::LBrace
+                | TokenKind::RParen
+                | TokenKind::Comma
+                | TokenKind::Eq
+                | TokenKind::KwFn
+                | TokenKind::KwStruct
+                | TokenKind::KwEnum
+                | TokenKind::KwType
+                | TokenKind::KwConst
+                | TokenKind::ProtocolKw
+                | TokenKind::Eof
+        )
+    }
+
+    /// True at an item keyword or end of input: nothing inside an item may run past these.
+    fn at_item_boundary(&self) -> bool {
+        matches!(
+            self.cur.kind,
+            TokenKind::KwFn
+                | TokenKind::KwStruct
+                | TokenKind::KwEnum
+                | TokenKind::KwType
+                | TokenKind::KwConst
+                | TokenKind::ProtocolKw
+                | TokenKind::KwImport
+                | TokenKind::KwMod
+                | TokenKind::Hash
+                | TokenKind::Eof
+        )
+    }
+
+    /// Skips to the end of a broken statement: consumes through the next `;`, or stops
+    /// in front of `}`, an item keyword or a keyword that starts a new statement.
+    fn synchronize_stmt(&mut self) {
+        loop {
+            match self.cur.kind {
+                TokenKind::Semi => {
+                    self.bump();
+                    return;
+                }
+                TokenKind::RBrace
+                | TokenKind::KwLet
+                | TokenKind::KwReturn
+                | TokenKind::KwIf
+                | TokenKind::KwFor
+                | TokenKind::KwMatch => return,
+                _ if self.at_item_boundary() => return,
+                _ => self.bump(),
+            }
+        }
+    }
+
     fn parse_program(&mut self) -> Program {
+        let mut imports = Vec::new();
+        let mut structs = Vec::new();
+        let mut enums = Vec::new();
+        let mut aliases = Vec::new();
+        let mut consts = Vec::new();
+        let mut labels = Vec::new();
         let mut funcs = Vec::new();
         let mut protocols = Vec::new();
         let start = Span::unknown(self.file.clone());
@@ -67,27 +189,350 @@ impl<'a> Parser<'a> {
         while self.cur.kind != TokenKind::Eof {
             match self.cur.kind {
                 TokenKind::KwFn => funcs.push(self.parse_fn()),
+                TokenKind::Hash => funcs.extend(self.parse_attributed_fn()),
+                TokenKind::KwStruct => structs.push(self.parse_struct()),
+                TokenKind::KwEnum => enums.push(self.parse_enum()),
+                TokenKind::KwType => aliases.push(self.parse_alias()),
+                TokenKind::KwConst => consts.push(self.parse_const()),
+                // `label` is contextual: it stays usable as a name elsewhere.
+                TokenKind::Ident(ref s) if s == "label" => labels.push(self.parse_label_decl()),
                 TokenKind::ProtocolKw => protocols.push(self.parse_protocol()),
+                TokenKind::KwImport | TokenKind::KwMod => imports.extend(self.parse_import()),
                 _ => {
                     let sp = self.cur.span.clone();
                     self.err(
                         "parse-expected-top-level",
-                        "Expected top-level item: `fn` or `protocol`.",
+                        "Expected top-level item: `fn`, `struct`, `enum`, `type`, `const`, `label`, `protocol`, `import`, `mod` or an attribute.",
                         sp,
                     );
-                    self.bump();
+                    // One diagnostic per junk run: skip to the next item.
+                    while !self.at_item_boundary() {
+                        self.bump();
+                    }
                 }
             }
         }
 
         Program {
+            imports,
+            structs,
+            enums,
+            aliases,
+            consts,
+            labels,
             funcs,
             protocols,
             span: start,
         }
     }
 
+    // import "dir/file.candy";   (module name = file stem)
+    // mod name;                  (same as import "name.candy";)
+    fn parse_import(&mut self) -> Option<ImportDecl> {
+        let kw_span = self.cur.span.clone();
+        let is_mod = self.cur.kind == TokenKind::KwMod;
+        self.bump(); // consume `import` / `mod`
+
+        let decl = if is_mod {
+            let name =
+                self.parse_ident("parse-expected-ident", "Expected module name after `mod`.");
+            (name.name != ERROR_NAME).then(|| ImportDecl {
+                path: format!("{}.candy", name.name),
+                name,
+                span: kw_span,
+            })
+        } else if let TokenKind::StrLit(path) = &self.cur.kind {
+            let path = path.clone();
+            let path_span = self.cur.span.clone();
+            self.bump();
+            match module_name_of(&path) {
+                Some(stem) => Some(ImportDecl {
+                    path,
+                    name: Ident {
+                        name: stem,
+                        span: path_span,
+                    },
+                    span: kw_span,
+                }),
+                None => {
+                    self.err(
+                        "import-invalid-path",
+                        "Import path must name a `.candy` file whose stem is a valid identifier.",
+                        path_span,
+                    );
+                    None
+                }
+            }
+        } else {
+            self.err(
+                "parse-expected-import-path",
+                "Expected a string path after `import`, e.g. `import \"crypto/keys.candy\";`.",
+                self.cur.span.clone(),
+            );
+            None
+        };
+
+        self.expect_kind(
+            TokenKind::Semi,
+            "parse-expected-semi",
+            "Expected `;` after import.",
+        );
+        decl
+    }
+
+    // type <Ident> = <Type>;
+    fn parse_alias(&mut self) -> TypeAlias {
+        let docs = self.take_docs();
+        let span = self.cur.span.clone();
+        self.bump(); // consume `type`
+
+        let name = self.parse_ident("parse-expected-ident", "Expected type alias name.");
+        self.expect_kind(
+            TokenKind::Eq,
+            "parse-expected-eq",
+            "Expected `=` after type alias name.",
+        );
+        let ty = self.parse_type();
+        self.expect_kind(
+            TokenKind::Semi,
+            "parse-expected-semi",
+            "Expected `;` after type alias.",
+        );
+
+        TypeAlias {
+            docs,
+            name,
+            ty,
+            span,
+        }
+    }
+
+    // const NAME: Type = expr;
+    fn parse_const(&mut self) -> ConstDecl {
+        let docs = self.take_docs();
+        let span = self.cur.span.clone();
+        self.bump(); // consume `const`
+
+        let name = self.parse_ident("parse-expected-ident", "Expected constant name.");
+        self.expect_kind(
+            TokenKind::Colon,
+            "parse-expected-colon",
+            "Expected `:` and a type after constant name.",
+        );
+        let ty = self.parse_type();
+        self.expect_kind(
+            TokenKind::Eq,
+            "parse-expected-eq",
+            "Expected `=` in constant declaration.",
+        );
+        let expr = self.parse_expr();
+        self.expect_kind(
+            TokenKind::Semi,
+            "parse-expected-semi",
+            "Expected `;` after constant.",
+        );
+
+        ConstDecl {
+            docs,
+            name,
+            ty,
+            expr,
+            span,
+        }
+    }
+
+    // label public < internal < secret;
+    fn parse_label_decl(&mut self) -> LabelDecl {
+        let docs = self.take_docs();
+        let span = self.cur.span.clone();
+        self.bump(); // consume `label`
+
+        let mut names = vec![self.parse_label_name()];
+        while self.cur.kind == TokenKind::Lt {
+            self.bump();
+            names.push(self.parse_label_name());
+        }
+        if names.len() < 2 {
+            let sp = self.cur.span.clone();
+            self.err(
+                "parse-expected-lt",
+                "A label order needs at least two labels, e.g. `label public < secret;`.",
+                sp,
+            );
+        }
+
+        self.expect_kind(
+            TokenKind::Semi,
+            "parse-expected-semi",
+            "Expected `<` or `;` in label declaration.",
+        );
+
+        LabelDecl { docs, names, span }
+    }
+
+    /// A label name; `secret` is allowed here even though it is a keyword.
+    fn parse_label_name(&mut self) -> Ident {
+        if self.cur.kind == TokenKind::KwSecret {
+            let span = self.cur.span.clone();
+            self.bump();
+            return Ident {
+                name: "secret".into(),
+                span,
+            };
+        }
+        self.parse_ident("parse-expected-ident", "Expected label name.")
+    }
+
+    // struct <Ident> { <ident>: <Type>, ... }   (trailing comma allowed)
+    fn parse_struct(&mut self) -> StructDecl {
+        let docs = self.take_docs();
+        let struct_span = self.cur.span.clone();
+        self.bump(); // consume `struct`
+
+        let name = self.parse_ident("parse-expected-ident", "Expected struct name identifier.");
+
+        self.expect_kind(
+            TokenKind::LBrace,
+            "parse-expected-lbrace",
+            "Expected `{` after struct name.",
+        );
+
+        let mut fields = Vec::new();
+        while self.cur.kind != TokenKind::RBrace && !self.at_item_boundary() {
+            let errors_before = self.report.diagnostics.len();
+            let fname = self.parse_ident("parse-expected-ident", "Expected field name.");
+            self.expect_kind(
+                TokenKind::Colon,
+                "parse-expected-colon",
+                "Expected `:` after field name.",
+            );
+            let ty = self.parse_type();
+            if fname.name != ERROR_NAME {
+                let sp = fname.span.clone();
+                fields.push(FieldDecl {
+                    name: fname,
+                    ty,
+                    span: sp,
+                });
+            }
+
+            if self.cur.kind == TokenKind::Comma {
+                self.bump();
+            } else if self.cur.kind != TokenKind::RBrace {
+                if self.report.diagnostics.len() == errors_before {
+                    self.err(
+                        "parse-expected-comma",
+                        "Expected `,` or `}` after struct field.",
+                        self.cur.span.clone(),
+                    );
+                }
+                // Resume at the next field or the end of the struct.
+                while !matches!(self.cur.kind, TokenKind::Comma | TokenKind::RBrace)
+                    && !self.at_item_boundary()
+                {
+                    self.bump();
+                }
+                if self.cur.kind == TokenKind::Comma {
+                    self.bump();
+                }
+            }
+        }
+
+        self.expect_kind(
+            TokenKind::RBrace,
+            "parse-expected-rbrace",
+            "Expected `}` to end struct.",
+        );
+
+        StructDecl {
+            docs,
+            name,
+            fields,
+            span: struct_span,
+        }
+    }
+
+    // enum <Ident> { <Variant>, <Variant>(<Type>, ...), ... }   (trailing comma allowed)
+    fn parse_enum(&mut self) -> EnumDecl {
+        let docs = self.take_docs();
+        let enum_span = self.cur.span.clone();
+        self.bump(); // consume `enum`
+
+        let name = self.parse_ident("parse-expected-ident", "Expected enum name identifier.");
+
+        self.expect_kind(
+            TokenKind::LBrace,
+            "parse-expected-lbrace",
+            "Expected `{` after enum name.",
+        );
+
+        let mut variants = Vec::new();
+        while self.cur.kind != TokenKind::RBrace && !self.at_item_boundary() {
+            let errors_before = self.report.diagnostics.len();
+            let vname = self.parse_ident("parse-expected-ident", "Expected variant name.");
+            let mut payload = Vec::new();
+            if self.cur.kind == TokenKind::LParen {
+                self.bump(); // consume `(`
+                while self.cur.kind != TokenKind::RParen && !self.at_sync_token() {
+                    payload.push(self.parse_type());
+                    if self.cur.kind == TokenKind::Comma {
+                        self.bump();
+                    } else {
+                        break;
+                    }
+                }
+                self.expect_kind(
+                    TokenKind::RParen,
+                    "parse-expected-rparen",
+                    "Expected `)` after variant payload.",
+                );
+            }
+            if vname.name != ERROR_NAME {
+                let sp = vname.span.clone();
+                variants.push(VariantDecl {
+                    name: vname,
+                    payload,
+                    span: sp,
+                });
+            }
+
+            if self.cur.kind == TokenKind::Comma {
+                self.bump();
+            } else if self.cur.kind != TokenKind::RBrace {
+                if self.report.diagnostics.len() == errors_before {
+                    self.err(
+                        "parse-expected-comma",
+                        "Expected `,` or `}` after enum variant.",
+                        self.cur.span.clone(),
+                    );
+                }
+                while !matches!(self.cur.kind, TokenKind::Comma | TokenKind::RBrace)
+                    && !self.at_item_boundary()
+                {
+                    self.bump();
+                }
+                if self.cur.kind == TokenKind::Comma {
+                    self.bump();
+                }
+            }
+        }
+
+        self.expect_kind(
+            TokenKind::RBrace,
+            "parse-expected-rbrace",
+            "Expected `}` to end enum.",
+        );
+
+        EnumDecl {
+            docs,
+            name,
+            variants,
+            span: enum_span,
+        }
+    }
+
     fn parse_fn(&mut self) -> FnDecl {
+        let docs = self.take_docs();
         let fn_span = match self.cur.kind {
             TokenKind::KwFn => {
                 let sp = self.cur.span.clone();
@@ -104,6 +549,12 @@ impl<'a> Parser<'a> {
 
         let name = self.parse_ident("parse-expected-ident", "Expected function name identifier.");
 
+        let generics = if self.cur.kind == TokenKind::Lt {
+            self.parse_generics()
+        } else {
+            vec![]
+        };
+
         self.expect_kind(
             TokenKind::LParen,
             "parse-expected-lparen",
@@ -126,6 +577,12 @@ impl<'a> Parser<'a> {
 
         let ret = self.parse_type();
 
+        // `ct` (constant-time) marker; contextual, so `ct` stays usable as a name.
+        let ct = matches!(&self.cur.kind, TokenKind::Ident(s) if s == "ct");
+        if ct {
+            self.bump();
+        }
+
         // v0.4: optional effects(...) after return type
         let effects = if self.cur.kind == TokenKind::KwEffects {
             self.parse_effects_clause()
@@ -136,15 +593,132 @@ impl<'a> Parser<'a> {
         let body = self.parse_block();
 
         FnDecl {
+            docs,
             name,
+            generics,
             params,
             ret,
             effects,
+            ct,
+            attrs: Vec::new(),
             body,
             span: fn_span,
         }
     }
 
+    /// `#[...]` attributes and the `fn` they belong to; doc comments may come before
+    /// or after the attributes.
+    fn parse_attributed_fn(&mut self) -> Option<FnDecl> {
+        let mut docs = self.take_docs();
+        let mut attrs = Vec::new();
+        while self.cur.kind == TokenKind::Hash {
+            attrs.extend(self.parse_attribute());
+            docs.append(&mut self.docs);
+        }
+        if self.cur.kind != TokenKind::KwFn {
+            let sp = self.cur.span.clone();
+            self.err(
+                "parse-attribute-target",
+                "Attributes are only allowed on functions.",
+                sp,
+            );
+            return None;
+        }
+        self.docs = docs;
+        let mut f = self.parse_fn();
+        f.attrs = attrs;
+        Some(f)
+    }
+
+    // #[allow(rule, ...)]   #[warn(rule, ...)]   #[deny(rule, ...)]
+    fn parse_attribute(&mut self) -> Option<Attribute> {
+        let start = self.cur.span.clone();
+        self.bump(); // consume `#`
+
+        let parsed = self.parse_attribute_body();
+        if parsed.is_none() {
+            // Skip the rest of the broken attribute.
+            while !matches!(self.cur.kind, TokenKind::RBracket) && !self.at_item_boundary() {
+                self.bump();
+            }
+            if self.cur.kind == TokenKind::RBracket {
+                self.bump();
+            }
+        }
+        let (level, rules, end) = parsed?;
+        Some(Attribute {
+            level,
+            rules,
+            span: start.join(&end),
+        })
+    }
+
+    fn parse_attribute_body(&mut self) -> Option<(LintLevel, Vec<Ident>, Span)> {
+        self.expect_kind(
+            TokenKind::LBracket,
+            "parse-expected-lbracket",
+            "Expected `[` after `#`.",
+        )?;
+        let level = match &self.cur.kind {
+            TokenKind::Ident(s) if s == "allow" => LintLevel::Allow,
+            TokenKind::Ident(s) if s == "warn" => LintLevel::Warn,
+            TokenKind::Ident(s) if s == "deny" => LintLevel::Deny,
+            _ => {
+                let sp = self.cur.span.clone();
+                self.err(
+                    "parse-unknown-attribute",
+                    "Unknown attribute; expected `allow`, `warn` or `deny`.",
+                    sp,
+                );
+                return None;
+            }
+        };
+        self.bump();
+        self.expect_kind(
+            TokenKind::LParen,
+            "parse-expected-lparen",
+            "Expected `(` and rule names after the attribute.",
+        )?;
+        let mut rules = Vec::new();
+        while self.cur.kind != TokenKind::RParen {
+            let rule = self.parse_rule_name();
+            if rule.name == ERROR_NAME {
+                return None;
+            }
+            rules.push(rule);
+            if self.cur.kind != TokenKind::Comma {
+                break;
+            }
+            self.bump();
+        }
+        self.expect_kind(
+            TokenKind::RParen,
+            "parse-expected-rparen",
+            "Expected `)` after rule names.",
+        )?;
+        let end = self.expect_kind(
+            TokenKind::RBracket,
+            "parse-expected-rbracket",
+            "Expected `]` to close the attribute.",
+        )?;
+        Some((level, rules, end))
+    }
+
+    /// A lint rule id such as `unused-variable`, kept as one `Ident`.
+    fn parse_rule_name(&mut self) -> Ident {
+        let mut id = self.parse_ident("parse-expected-ident", "Expected a lint rule name.");
+        while id.name != ERROR_NAME && self.cur.kind == TokenKind::Minus {
+            self.bump();
+            let part = self.parse_ident("parse-expected-ident", "Expected a lint rule name.");
+            if part.name == ERROR_NAME {
+                return part;
+            }
+            id.name = format!("{}-{}", id.name, part.name);
+            id.span = id.span.join(&part.span);
+        }
+        id
+    }
+
     fn parse_effects_clause(&mut self) -> Vec<EffectSpec> {
         // effects(io, time, rand, net)
         let kw_span = self.cur.span.clone();
@@ -193,6 +767,7 @@ impl<'a> Parser<'a> {
                     "net" => Some(Effect::Net),
                     "time" => Some(Effect::Time),
                     "rand" => Some(Effect::Rand),
+                    "declassify" => Some(Effect::Declassify),
                     _ => None,
                 };
                 self.bump();
@@ -201,7 +776,7 @@ impl<'a> Parser<'a> {
                     None => {
                         self.err(
                             "parse-unknown-effect",
-                            "Unknown effect (expected io|net|time|rand).",
+                            "Unknown effect (expected io|net|time|rand|declassify).",
                             sp.clone(),
                         );
                         (Effect::Io, sp) // recovery placeholder
@@ -212,7 +787,7 @@ impl<'a> Parser<'a> {
                 let sp = self.cur.span.clone();
                 self.err(
                     "parse-expected-effect",
-                    "Expected effect name (io|net|time|rand).",
+                    "Expected effect name (io|net|time|rand|declassify).",
                     sp.clone(),
                 );
                 self.bump();
@@ -221,21 +796,65 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn parse_params(&mut self) -> Vec<Param> {
-        // v0.2 minimal: either empty list or a single param `ident : Type`
-        if self.cur.kind == TokenKind::RParen {
-            return vec![];
+    fn parse_generics(&mut self) -> Vec<GenericParam> {
+        // <T, U: Copy + Other>
+        self.bump(); // consume `<`
+        let mut out = Vec::new();
+        while self.cur.kind != TokenKind::Gt && !self.at_item_boundary() {
+            let name = self.parse_ident("parse-expected-ident", "Expected type parameter name.");
+            let mut bounds = Vec::new();
+            if self.cur.kind == TokenKind::Colon {
+                self.bump();
+                loop {
+                    bounds.push(self.parse_ident("parse-expected-ident", "Expected bound name."));
+                    if self.cur.kind != TokenKind::Plus {
+                        break;
+                    }
+                    self.bump();
+                }
+            }
+            let sp = name.span.clone();
+            out.push(GenericParam {
+                name,
+                bounds,
+                span: sp,
+            });
+
+            if self.cur.kind == TokenKind::Comma {
+                self.bump();
+            } else {
+                break;
+            }
         }
-
-        let name = self.parse_ident("parse-expected-ident", "Expected parameter name.");
         self.expect_kind(
-            TokenKind::Colon,
-            "parse-expected-colon",
-            "Expected `:` after parameter name.",
+            TokenKind::Gt,
+            "parse-expected-gt",
+            "Expected `>` after type parameters.",
         );
-        let ty = self.parse_type();
-        let sp = name.span.clone();
-        vec![Param { name, ty, span: sp }]
+        out
+    }
+
+    fn parse_params(&mut self) -> Vec<Param> {
+        // `ident : Type` separated by commas, trailing comma allowed
+        let mut out = Vec::new();
+        while self.cur.kind != TokenKind::RParen && !self.at_item_boundary() {
+            let name = self.parse_ident("parse-expected-ident", "Expected parameter name.");
+            self.expect_kind(
+                TokenKind::Colon,
+                "parse-expected-colon",
+                "Expected `:` after parameter name.",
+            );
+            let ty = self.parse_type();
+            let sp = name.span.clone();
+            out.push(Param { name, ty, span: sp });
+
+            if self.cur.kind == TokenKind::Comma {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        out
     }
 
     fn parse_block(&mut self) -> Block {
@@ -248,7 +867,7 @@ impl<'a> Parser<'a> {
             .unwrap_or_else(|| Span::unknown(self.file.clone()));
 
         let mut stmts = vec![];
-        while self.cur.kind != TokenKind::RBrace && self.cur.kind != TokenKind::Eof {
+        while self.cur.kind != TokenKind::RBrace && !self.at_item_boundary() {
             stmts.push(self.parse_stmt());
         }
 
@@ -265,28 +884,47 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_stmt(&mut self) -> Stmt {
-        match self.cur.kind {
+        let errors_before = self.report.diagnostics.len();
+        let stmt = match self.cur.kind {
             TokenKind::KwLet => self.parse_let(),
             TokenKind::KwReturn => self.parse_return(),
             TokenKind::KwIf => self.parse_if(),
+            TokenKind::KwFor => self.parse_for(),
+            TokenKind::KwMatch => self.parse_match(),
             _ => {
                 let expr = self.parse_expr();
-                let semi = self
-                    .expect_kind(
-                        TokenKind::Semi,
-                        "parse-expected-semi",
-                        "Expected `;` after expression.",
-                    )
-                    .unwrap_or_else(|| expr.span().clone());
-                Stmt::Expr { expr, span: semi }
+                if let Expr::Error { span } = expr {
+                    Stmt::Error { span }
+                } else if self.cur.kind == TokenKind::Eq {
+                    self.parse_assign(expr)
+                } else {
+                    let semi = self
+                        .expect_kind(
+                            TokenKind::Semi,
+                            "parse-expected-semi",
+                            "Expected `;` after expression.",
+                        )
+                        .unwrap_or_else(|| expr.span().clone());
+                    Stmt::Expr { expr, span: semi }
+                }
             }
+        };
+
+        if self.report.diagnostics.len() > errors_before {
+            self.synchronize_stmt();
         }
+        stmt
     }
 
     fn parse_let(&mut self) -> Stmt {
         let let_span = self.cur.span.clone();
         self.bump();
 
+        let mutable = self.cur.kind == TokenKind::KwMut;
+        if mutable {
+            self.bump(); // consume `mut`
+        }
+
         let name = self.parse_ident("parse-expected-ident", "Expected identifier after `let`.");
 
         let mut ty = None;
@@ -311,12 +949,42 @@ impl<'a> Parser<'a> {
 
         Stmt::Let {
             name,
+            mutable,
             ty,
             expr,
             span: let_span,
         }
     }
 
+    // <ident> = <expr>;   (`lhs` is already parsed, `cur` is `=`)
+    fn parse_assign(&mut self, lhs: Expr) -> Stmt {
+        self.bump(); // consume `=`
+        let rhs = self.parse_expr();
+
+        self.expect_kind(
+            TokenKind::Semi,
+            "parse-expected-semi",
+            "Expected `;` after assignment.",
+        );
+
+        match lhs {
+            Expr::Var { name, span } => Stmt::Assign {
+                name,
+                expr: rhs,
+                span,
+            },
+            other => {
+                let sp = other.span().clone();
+                self.err(
+                    "parse-invalid-assign-target",
+                    "Left-hand side of `=` must be a variable.",
+                    sp.clone(),
+                );
+                Stmt::Error { span: sp }
+            }
+        }
+    }
+
     fn parse_return(&mut self) -> Stmt {
         let ret_span = self.cur.span.clone();
         self.bump();
@@ -374,8 +1042,390 @@ impl<'a> Parser<'a> {
         }
     }
 
+    // for <ident> in <expr>..<expr> { ... }
+    fn parse_for(&mut self) -> Stmt {
+        let for_span = self.cur.span.clone();
+        self.bump(); // consume `for`
+
+        let var = self.parse_ident(
+            "parse-expected-ident",
+            "Expected loop variable after `for`.",
+        );
+
+        self.expect_kind(
+            TokenKind::KwIn,
+            "parse-expected-in",
+            "Expected `in` after loop variable.",
+        );
+
+        let outer = std::mem::replace(&mut self.no_struct_lit, true);
+        let start = self.parse_expr();
+
+        self.expect_kind(
+            TokenKind::DotDot,
+            "parse-expected-range",
+            "Expected `..` in loop range (`for i in 0..N`).",
+        );
+
+        let end = self.parse_expr();
+        self.no_struct_lit = outer;
+        let body = self.parse_block();
+
+        Stmt::For {
+            var,
+            start,
+            end,
+            body,
+            span: for_span,
+        }
+    }
+
+    // match <expr> { <pattern> => { ... } ... }   (`,` between arms optional)
+    fn parse_match(&mut self) -> Stmt {
+        let match_span = self.cur.span.clone();
+        self.bump(); // consume `match`
+
+        let outer = std::mem::replace(&mut self.no_struct_lit, true);
+        let scrutinee = self.parse_expr();
+        self.no_struct_lit = outer;
+
+        self.expect_kind(
+            TokenKind::LBrace,
+            "parse-expected-lbrace",
+            "Expected `{` after match scrutinee.",
+        );
+
+        let mut arms = Vec::new();
+        while self.cur.kind != TokenKind::RBrace && !self.at_item_boundary() {
+            let errors_before = self.report.diagnostics.len();
+            let pattern = self.parse_pattern();
+            self.expect_kind(
+                TokenKind::FatArrow,
+                "parse-expected-fat-arrow",
+                "Expected `=>` after match pattern.",
+            );
+            if self.report.diagnostics.len() > errors_before {
+                // Resume at the arm body, or give up on the rest of the match.
+                while !matches!(self.cur.kind, TokenKind::LBrace | TokenKind::RBrace)
+                    && !self.at_item_boundary()
+                {
+                    self.bump();
+                }
+                if self.cur.kind != TokenKind::LBrace {
+                    break;
+                }
+            }
+            let body = self.parse_block();
+            let span = pattern.span().clone();
+            arms.push(MatchArm {
+                pattern,
+                body,
+                span,
+            });
+            if self.cur.kind == TokenKind::Comma {
+                self.bump();
+            }
+        }
+
+        self.expect_kind(
+            TokenKind::RBrace,
+            "parse-expected-rbrace",
+            "Expected `}` to end match.",
+        );
+
+        Stmt::Match {
+            scrutinee,
+            arms,
+            span: match_span,
+        }
+    }
+
+    // _ | true | false | Enum::Variant | Enum::Variant(<ident>, ...)
+    fn parse_pattern(&mut self) -> Pattern {
+        let sp = self.cur.span.clone();
+        match &self.cur.kind {
+            TokenKind::Ident(s) if s == "_" => {
+                self.bump();
+                Pattern::Wildcard { span: sp }
+            }
+            TokenKind::Ident(s) if s == "true" || s == "false" => {
+                let value = s == "true";
+                self.bump();
+                Pattern::BoolLit { value, span: sp }
+            }
+            TokenKind::Ident(_) => {
+                let path = self.parse_path();
+                let mut bindings = Vec::new();
+                if self.cur.kind == TokenKind::LParen {
+                    self.bump(); // consume `(`
+                    while self.cur.kind != TokenKind::RParen && !self.at_sync_token() {
+                        bindings.push(
+                            self.parse_ident("parse-expected-ident", "Expected binding name."),
+                        );
+                        if self.cur.kind == TokenKind::Comma {
+                            self.bump();
+                        } else {
+                            break;
+                        }
+                    }
+                    self.expect_kind(
+                        TokenKind::RParen,
+                        "parse-expected-rparen",
+                        "Expected `)` after pattern bindings.",
+                    );
+                }
+                let span = path.span.clone();
+                Pattern::Variant {
+                    path,
+                    bindings,
+                    span,
+                }
+            }
+            _ => {
+                self.err(
+                    "parse-expected-pattern",
+                    "Expected a pattern (`Enum::Variant`, `true`, `false` or `_`).",
+                    sp.clone(),
+                );
+                Pattern::Wildcard { span: sp }
+            }
+        }
+    }
+
     fn parse_expr(&mut self) -> Expr {
+        self.parse_binary(1)
+    }
+
+    /// Binary operator at `cur` with its precedence (higher binds tighter).
+    fn peek_binop(&self) -> Option<(BinOp, u8)> {
+        let op = match self.cur.kind {
+            TokenKind::OrOr => (BinOp::Or, 1),
+            TokenKind::AndAnd => (BinOp::And, 2),
+            TokenKind::EqEq => (BinOp::Eq, 3),
+            TokenKind::NotEq => (BinOp::Ne, 3),
+            TokenKind::Lt => (BinOp::Lt, 3),
+            TokenKind::Le => (BinOp::Le, 3),
+            TokenKind::Gt => (BinOp::Gt, 3),
+            TokenKind::Ge => (BinOp::Ge, 3),
+            TokenKind::Plus => (BinOp::Add, 4),
+            TokenKind::Minus => (BinOp::Sub, 4),
+            TokenKind::Star => (BinOp::Mul, 5),
+            TokenKind::Slash => (BinOp::Div, 5),
+            TokenKind::Percent => (BinOp::Rem, 5),
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    // Precedence climbing. All operators are left-associative except comparisons,
+    // which do not chain (`a < b < c` is rejected like in Rust).
+    fn parse_binary(&mut self, min_prec: u8) -> Expr {
+        let mut lhs = self.parse_unary();
+        let mut chained_cmp = false;
+
+        while let Some((op, prec)) = self.peek_binop() {
+            if prec < min_prec {
+                break;
+            }
+            if op.is_comparison() {
+                if chained_cmp {
+                    self.err(
+                        "parse-chained-comparison",
+                        "Comparison operators cannot be chained; use `&&` or parentheses.",
+                        self.cur.span.clone(),
+                    );
+                }
+                chained_cmp = true;
+            }
+            self.bump(); // consume operator
+
+            let rhs = self.parse_binary(prec + 1);
+            let span = lhs.span().join(rhs.span());
+            lhs = Expr::Binary {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+                span,
+            };
+        }
+
+        lhs
+    }
+
+    fn parse_unary(&mut self) -> Expr {
+        let op = match self.cur.kind {
+            TokenKind::Minus => UnOp::Neg,
+            TokenKind::Bang => UnOp::Not,
+            _ => return self.parse_postfix(),
+        };
+        let op_span = self.cur.span.clone();
+        self.bump(); // consume operator
+
+        let expr = self.parse_unary();
+        let span = op_span.join(expr.span());
+        Expr::Unary {
+            op,
+            expr: Box::new(expr),
+            span,
+        }
+    }
+
+    // <primary> ( . <ident> | [ <expr> ] | [ <expr> .. <expr> ] )*
+    fn parse_postfix(&mut self) -> Expr {
+        let mut e = self.parse_primary();
+        loop {
+            match self.cur.kind {
+                TokenKind::Dot => {
+                    self.bump(); // consume `.`
+                    let field =
+                        self.parse_ident("parse-expected-ident", "Expected field name after `.`.");
+                    if field.name == ERROR_NAME {
+                        return Expr::Error {
+                            span: e.span().clone(),
+                        };
+                    }
+                    let span = e.span().join(&field.span);
+                    e = Expr::Field {
+                        base: Box::new(e),
+                        field,
+                        span,
+                    };
+                }
+                TokenKind::LBracket => {
+                    self.bump(); // consume `[`
+                    let outer = std::mem::replace(&mut self.no_struct_lit, false);
+                    let start = self.parse_expr();
+                    let end = if self.cur.kind == TokenKind::DotDot {
+                        self.bump(); // consume `..`
+                        Some(self.parse_expr())
+                    } else {
+                        None
+                    };
+                    self.no_struct_lit = outer;
+                    let close = self
+                        .expect_kind(
+                            TokenKind::RBracket,
+                            "parse-expected-rbracket",
+                            "Expected `]` after index.",
+                        )
+                        .unwrap_or_else(|| start.span().clone());
+                    let span = e.span().join(&close);
+                    e = match end {
+                        Some(end) => Expr::Slice {
+                            base: Box::new(e),
+                            start: Box::new(start),
+                            end: Box::new(end),
+                            span,
+                        },
+                        None => Expr::Index {
+                            base: Box::new(e),
+                            index: Box::new(start),
+                            span,
+                        },
+                    };
+                }
+                _ => return e,
+            }
+        }
+    }
+
+    // [ <expr>, ... ]  or  [ <expr>; <count> ]   (`cur` is `[`)
+    fn parse_array_lit(&mut self) -> Expr {
+        let open = self.cur.span.clone();
+        self.bump(); // consume `[`
+        let outer = std::mem::replace(&mut self.no_struct_lit, false);
+
+        let mut elems = Vec::new();
+        let mut count = None;
+        if self.cur.kind != TokenKind::RBracket {
+            elems.push(self.parse_expr());
+            if self.cur.kind == TokenKind::Semi {
+                self.bump(); // consume `;`
+                count = Some(self.parse_expr());
+            } else {
+                while self.cur.kind == TokenKind::Comma {
+                    self.bump();
+                    if self.cur.kind == TokenKind::RBracket {
+                        break; // trailing comma
+                    }
+                    elems.push(self.parse_expr());
+                }
+            }
+        }
+        self.no_struct_lit = outer;
+
+        let close = self
+            .expect_kind(
+                TokenKind::RBracket,
+                "parse-expected-rbracket",
+                "Expected `]` to end array literal.",
+            )
+            .unwrap_or_else(|| open.clone());
+        let span = open.join(&close);
+        match count {
+            Some(count) => Expr::ArrayRepeat {
+                elem: Box::new(elems.pop().expect("one element parsed")),
+                count: Box::new(count),
+                span,
+            },
+            None => Expr::ArrayLit { elems, span },
+        }
+    }
+
+    // Name { <ident>: <expr>, ... }   (`name` already parsed, `cur` is `{`)
+    fn parse_struct_lit(&mut self, name: Ident) -> Expr {
+        self.bump(); // consume `{`
+
+        let mut fields = Vec::new();
+        while self.cur.kind != TokenKind::RBrace && !self.at_item_boundary() {
+            let fname = self.parse_ident("parse-expected-ident", "Expected field name.");
+            self.expect_kind(
+                TokenKind::Colon,
+                "parse-expected-colon",
+                "Expected `:` after field name.",
+            );
+            let expr = self.parse_expr();
+            if fname.name != ERROR_NAME {
+                let sp = fname.span.clone();
+                fields.push(FieldInit {
+                    name: fname,
+                    expr,
+                    span: sp,
+                });
+            }
+            if self.cur.kind == TokenKind::Comma {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+
+        let end = self
+            .expect_kind(
+                TokenKind::RBrace,
+                "parse-expected-rbrace",
+                "Expected `}` to end struct literal.",
+            )
+            .unwrap_or_else(|| name.span.clone());
+        let span = name.span.join(&end);
+        Expr::StructLit { name, fields, span }
+    }
+
+    fn parse_primary(&mut self) -> Expr {
         match &self.cur.kind {
+            TokenKind::LBracket => self.parse_array_lit(),
+            TokenKind::LParen => {
+                self.bump(); // consume '('
+                let outer = std::mem::replace(&mut self.no_struct_lit, false);
+                let e = self.parse_expr();
+                self.no_struct_lit = outer;
+                self.expect_kind(
+                    TokenKind::RParen,
+                    "parse-expected-rparen",
+                    "Expected `)` to close parenthesized expression.",
+                );
+                e
+            }
             TokenKind::IntLit(v) => {
                 let sp = self.cur.span.clone();
                 let vv = *v;
@@ -401,7 +1451,7 @@ impl<'a> Parser<'a> {
                 Expr::BoolLit { value: b, span: sp }
             }
             TokenKind::Ident(s) if s == "move" => {
-                // move(<ident>)
+                // move(<ident>) or move(<ident>.<field>...)
                 let move_span = self.cur.span.clone();
                 self.bump(); // consume "move"
 
@@ -415,6 +1465,13 @@ impl<'a> Parser<'a> {
                     "parse-expected-ident",
                     "Expected identifier inside move(...).",
                 );
+                let mut fields = Vec::new();
+                while self.cur.kind == TokenKind::Dot {
+                    self.bump(); // consume `.`
+                    fields.push(
+                        self.parse_ident("parse-expected-ident", "Expected field name after `.`."),
+                    );
+                }
 
                 self.expect_kind(
                     TokenKind::RParen,
@@ -422,14 +1479,18 @@ impl<'a> Parser<'a> {
                     "Expected `)` after move argument.",
                 );
 
+                if name.name == ERROR_NAME || fields.iter().any(|f| f.name == ERROR_NAME) {
+                    return Expr::Error { span: move_span };
+                }
                 Expr::Move {
                     name,
+                    fields,
                     span: move_span,
                 }
             }
             TokenKind::Ident(_) => {
-                // identifier or call
-                let id = self.parse_ident("parse-expected-ident", "Expected identifier.");
+                // identifier or call, possibly qualified: `keys::derive(...)`
+                let id = self.parse_path();
                 let start_sp = id.span.clone();
 
                 if self.cur.kind == TokenKind::LParen {
@@ -437,6 +1498,7 @@ impl<'a> Parser<'a> {
                     let call_span = start_sp.clone();
                     self.bump(); // consume '('
 
+                    let outer = std::mem::replace(&mut self.no_struct_lit, false);
                     let mut args = Vec::new();
                     if self.cur.kind != TokenKind::RParen {
                         loop {
@@ -456,12 +1518,15 @@ impl<'a> Parser<'a> {
                         "parse-expected-rparen",
                         "Expected `)` after call arguments.",
                     );
+                    self.no_struct_lit = outer;
 
                     Expr::Call {
                         callee: id,
                         args,
                         span: call_span,
                     }
+                } else if self.cur.kind == TokenKind::LBrace && !self.no_struct_lit {
+                    self.parse_struct_lit(id)
                 } else {
                     Expr::Var {
                         name: id,
@@ -476,14 +1541,10 @@ impl<'a> Parser<'a> {
                     "Unexpected token in expression.",
                     sp.clone(),
                 );
-                self.bump();
-                Expr::Var {
-                    name: Ident {
-                        name: "_error_".into(),
-                        span: sp.clone(),
-                    },
-                    span: sp,
+                if !self.at_sync_token() {
+                    self.bump();
                 }
+                Expr::Error { span: sp }
             }
         }
     }
@@ -499,33 +1560,98 @@ impl<'a> Parser<'a> {
                     span: sp,
                 }
             }
-            TokenKind::Ident(s) => {
-                let sp = self.cur.span.clone();
-                let name = s.clone();
-                self.bump();
-                match name.as_str() {
+            TokenKind::Ident(_) => {
+                // possibly qualified: `keys::KeyPair`
+                let id = self.parse_path();
+                let sp = id.span;
+                match id.name.as_str() {
+                    "Bytes" => self.parse_bytes_len(sp),
                     "Int" => Type::Int { span: sp },
                     "Bool" => Type::Bool { span: sp },
                     "Unit" => Type::Unit { span: sp },
-                    _ => Type::Named { name, span: sp },
+                    "Str" => Type::Str { span: sp },
+                    "labeled" if self.cur.kind == TokenKind::Lt => self.parse_labeled(sp),
+                    _ => Type::Named {
+                        name: id.name,
+                        span: sp,
+                    },
                 }
             }
             _ => {
                 let sp = self.cur.span.clone();
                 self.err(
                     "parse-expected-type",
-                    "Expected type name (Int|Bool|Unit|...).",
+                    "Expected type name (Int|Bool|Str|Unit|...).",
                     sp.clone(),
                 );
-                self.bump();
-                Type::Named {
-                    name: "_error_".into(),
-                    span: sp,
+                if !self.at_sync_token() {
+                    self.bump();
                 }
+                Type::Error { span: sp }
             }
         }
     }
 
+    // labeled<name> T   (`labeled` already consumed)
+    fn parse_labeled(&mut self, kw_span: Span) -> Type {
+        self.bump(); // consume `<`
+        let label = self.parse_label_name();
+        self.expect_kind(
+            TokenKind::Gt,
+            "parse-expected-gt",
+            "Expected `>` after label name.",
+        );
+        let inner = self.parse_type();
+        Type::Labeled {
+            label,
+            inner: Box::new(inner),
+            span: kw_span,
+        }
+    }
+
+    // Bytes[<expr>]   (`Bytes` already consumed)
+
+    fn parse_bytes_len(&mut self, bytes_span: Span) -> Type {
+        if self
+            .expect_kind(
+                TokenKind::LBracket,
+                "parse-expected-lbracket",
+                "Expected `[` after `Bytes` (e.g. `Bytes[32]`).",
+            )
+            .is_none()
+        {
+            return Type::Error { span: bytes_span };
+        }
+        let len = self.parse_expr();
+        let end = self
+            .expect_kind(
+                TokenKind::RBracket,
+                "parse-expected-rbracket",
+                "Expected `]` after array length.",
+            )
+            .unwrap_or_else(|| len.span().clone());
+        if matches!(len, Expr::Error { .. }) {
+            return Type::Error { span: bytes_span };
+        }
+        Type::Bytes {
+            len: Box::new(len),
+            span: bytes_span.join(&end),
+        }
+    }
+
+    /// `a` or `a::b::c`; a qualified path is kept as one `Ident` named "a::b::c"
+    /// whose span covers the whole path.
+    fn parse_path(&mut self) -> Ident {
+        let mut id = self.parse_ident("parse-expected-ident", "Expected identifier.");
+        while self.cur.kind == TokenKind::ColonColon {
+            self.bump(); // consume `::`
+            let seg = self.parse_ident("parse-expected-ident", "Expected identifier after `::`.");
+            id.name = format!("{}::{}", id.name, seg.name);
+            id.span = id.span.join(&seg.span);
+        }
+        id
+    }
+
     fn parse_ident(&mut self, code: &str, msg: &str) -> Ident {
         match &self.cur.kind {
             TokenKind::Ident(s) => {
@@ -537,9 +1663,11 @@ impl<'a> Parser<'a> {
             _ => {
                 let sp = self.cur.span.clone();
                 self.err(code, msg, sp.clone());
-                self.bump();
+                if !self.at_sync_token() {
+                    self.bump();
+                }
                 Ident {
-                    name: "_error_".into(),
+                    name: ERROR_NAME.into(),
                     span: sp,
                 }
             }
@@ -555,6 +1683,7 @@ impl<'a> Parser<'a> {
     // - We keep it intentionally small: only `state` and `transition` items in a protocol body.
     // - Errors are reported with spans; we try to recover by bumping.
     fn parse_protocol(&mut self) -> ProtocolDecl {
+        let docs = self.take_docs();
         // consume `protocol`
         let proto_span = self.cur.span.clone();
         self.bump();
@@ -570,7 +1699,7 @@ impl<'a> Parser<'a> {
         let mut states: Vec<StateDecl> = Vec::new();
         let mut transitions: Vec<TransitionDecl> = Vec::new();
 
-        while self.cur.kind != TokenKind::RBrace && self.cur.kind != TokenKind::Eof {
+        while self.cur.kind != TokenKind::RBrace && !self.at_item_boundary() {
             match self.cur.kind {
                 TokenKind::StateKw | TokenKind::FinalKw => {
                     states.push(self.parse_protocol_state());
@@ -585,7 +1714,7 @@ impl<'a> Parser<'a> {
                         "Unexpected token in protocol body (expected `state` or `transition`).",
                         sp.clone(),
                     );
-                    self.bump(); // recovery
+                    self.synchronize_protocol_item();
                 }
             }
         }
@@ -597,17 +1726,38 @@ impl<'a> Parser<'a> {
         );
 
         ProtocolDecl {
+            docs,
             name,
             states,
             transitions,
             span: proto_span,
         }
     }
+    /// Skips a broken protocol item: through the next `;`, or up to the next
+    /// `state`/`final`/`transition`, `}` or item keyword.
+    fn synchronize_protocol_item(&mut self) {
+        loop {
+            match self.cur.kind {
+                TokenKind::Semi => {
+                    self.bump();
+                    return;
+                }
+                TokenKind::StateKw
+                | TokenKind::FinalKw
+                | TokenKind::TransitionKw
+                | TokenKind::RBrace => return,
+                _ if self.at_item_boundary() => return,
+                _ => self.bump(),
+            }
+        }
+    }
+
     fn parse_protocol_state(&mut self) -> StateDecl {
         // Accept:
         //   state Name;
         //   final state Name;
 
+        let docs = self.take_docs();
         let start_span = self.cur.span.clone();
 
         let mut is_final = false;
@@ -631,6 +1781,7 @@ impl<'a> Parser<'a> {
         );
 
         StateDecl {
+            docs,
             name,
             is_final,
             span: start_span,
@@ -638,6 +1789,7 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_protocol_transition(&mut self) -> TransitionDecl {
+        let docs = self.take_docs();
         let tr_span = self.cur.span.clone(); // `transition`
         self.bump(); // consume `transition`
 
@@ -664,6 +1816,7 @@ impl<'a> Parser<'a> {
         );
 
         TransitionDecl {
+            docs,
             from,
             to,
             span: tr_span,
@@ -0,0 +1,365 @@
+// Multi-file programs (v0.6)
+//
+// `parse_file_with_imports` parses a root file, resolves its `import`/`mod` declarations
+// relative to the importing file, and merges every module into one flat `Program`:
+//
+// - items of an imported module are renamed to their qualified name (`keys::derive`);
+// - calls and struct type names inside each module are rewritten to those names (`derive`
+//   inside `keys`, `keys::derive` in the importer), so later passes only see one namespace;
+// - each file is loaded once, even when imported from several places;
+// - spans keep pointing at the file the code came from.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use candy_ast::{Block, Expr, Pattern, Program, Stmt, Type};
+use candy_diagnostics::{Diagnostic, DiagnosticReport, Span};
+
+use crate::parse_file_with_report;
+
+/// Parses `file` (whose contents are `src`) and every module it imports, transitively.
+/// Imported files are read from disk relative to the importing file.
+pub fn parse_file_with_imports(file: &str, src: &str) -> (Program, DiagnosticReport) {
+    let mut ld = Loader::default();
+    let key = fs::canonicalize(file).unwrap_or_else(|_| PathBuf::from(file));
+    ld.load_source(key, file.to_string(), src, String::new());
+
+    let mut out = Program {
+        imports: Vec::new(),
+        structs: Vec::new(),
+        enums: Vec::new(),
+        aliases: Vec::new(),
+        consts: Vec::new(),
+        labels: Vec::new(),
+        funcs: Vec::new(),
+        protocols: Vec::new(),
+        span: Span::unknown(file.to_string()),
+    };
+    // Slot 0 is the root module: its items come first.
+    for (i, m) in ld.modules.into_iter().flatten().enumerate() {
+        if i == 0 {
+            out.imports = m.imports;
+            out.span = m.span;
+        }
+        out.structs.extend(m.structs);
+        out.enums.extend(m.enums);
+        out.aliases.extend(m.aliases);
+        out.consts.extend(m.consts);
+        // Labels are program-wide, so their names are not qualified.
+        out.labels.extend(m.labels);
+        out.funcs.extend(m.funcs);
+        out.protocols.extend(m.protocols);
+    }
+    (out, ld.report)
+}
+
+#[derive(Default)]
+struct Loader {
+    report: DiagnosticReport,
+    /// Canonical path -> qualified module prefix ("" for the root).
+    loaded: HashMap<PathBuf, String>,
+    /// Modules currently being loaded, outermost first (for cycle detection).
+    stack: Vec<(PathBuf, String)>,
+    /// Rewritten modules in load order; a slot is reserved before the module's imports load.
+    modules: Vec<Option<Program>>,
+}
+
+impl Loader {
+    /// Loads the module imported by `imp_span` from `display`; returns its prefix.
+    fn load_import(&mut self, display: String, prefix: String, imp_span: &Span) -> Option<String> {
+        let Ok(key) = fs::canonicalize(&display) else {
+            self.report.push(Diagnostic::error(
+                "import-not-found",
+                format!("Imported file `{display}` not found."),
+                imp_span.clone(),
+            ));
+            return None;
+        };
+
+        if let Some(pos) = self.stack.iter().position(|(k, _)| *k == key) {
+            let mut chain: Vec<&str> = self.stack[pos..].iter().map(|(_, d)| d.as_str()).collect();
+            chain.push(&display);
+            self.report.push(Diagnostic::error(
+                "import-cycle",
+                format!("Import cycle: {}.", chain.join(" -> ")),
+                imp_span.clone(),
+            ));
+            return None;
+        }
+
+        if let Some(existing) = self.loaded.get(&key) {
+            return Some(existing.clone());
+        }
+
+        let src = match fs::read_to_string(&key) {
+            Ok(s) => s,
+            Err(e) => {
+                self.report.push(Diagnostic::error(
+                    "import-not-found",
+                    format!("Failed to read imported file `{display}`: {e}"),
+                    imp_span.clone(),
+                ));
+                return None;
+            }
+        };
+
+        self.load_source(key, display, &src, prefix.clone());
+        Some(prefix)
+    }
+
+    fn load_source(&mut self, key: PathBuf, display: String, src: &str, prefix: String) {
+        let (mut prog, mut r) = parse_file_with_report(&display, src);
+        self.report.diagnostics.append(&mut r.diagnostics);
+
+        self.loaded.insert(key.clone(), prefix.clone());
+        let slot = self.modules.len();
+        self.modules.push(None);
+        self.stack.push((key, display.clone()));
+
+        // Local module name -> qualified prefix of the imported module.
+        let mut aliases: HashMap<String, String> = HashMap::new();
+        let dir = Path::new(&display).parent().unwrap_or(Path::new(""));
+        for imp in &prog.imports {
+            if aliases.contains_key(&imp.name.name) {
+                self.report.push(Diagnostic::error(
+                    "import-duplicate",
+                    format!("Module name `{}` is imported twice.", imp.name.name),
+                    imp.span.clone(),
+                ));
+                continue;
+            }
+            let child_display = dir.join(&imp.path).to_string_lossy().into_owned();
+            let child_prefix = qualify(&prefix, &imp.name.name);
+            if let Some(p) = self.load_import(child_display, child_prefix, &imp.span) {
+                aliases.insert(imp.name.name.clone(), p);
+            }
+        }
+
+        self.stack.pop();
+
+        let scope = Scope {
+            prefix: &prefix,
+            local_items: prog
+                .funcs
+                .iter()
+                .map(|f| f.name.name.clone())
+                .chain(prog.structs.iter().map(|s| s.name.name.clone()))
+                .chain(prog.enums.iter().map(|e| e.name.name.clone()))
+                .chain(prog.aliases.iter().map(|a| a.name.name.clone()))
+                .chain(prog.consts.iter().map(|c| c.name.name.clone()))
+                .collect(),
+            consts: prog.consts.iter().map(|c| c.name.name.clone()).collect(),
+            aliases,
+        };
+        for s in &mut prog.structs {
+            s.name.name = qualify(&prefix, &s.name.name);
+            for fd in &mut s.fields {
+                rewrite_type(&mut fd.ty, &scope);
+            }
+        }
+        for e in &mut prog.enums {
+            e.name.name = qualify(&prefix, &e.name.name);
+            for v in &mut e.variants {
+                for t in &mut v.payload {
+                    rewrite_type(t, &scope);
+                }
+            }
+        }
+        for a in &mut prog.aliases {
+            a.name.name = qualify(&prefix, &a.name.name);
+            rewrite_type(&mut a.ty, &scope);
+        }
+        for c in &mut prog.consts {
+            c.name.name = qualify(&prefix, &c.name.name);
+            rewrite_type(&mut c.ty, &scope);
+            rewrite_expr(&mut c.expr, &scope, &[]);
+        }
+        for f in &mut prog.funcs {
+            f.name.name = qualify(&prefix, &f.name.name);
+            for p in &mut f.params {
+                rewrite_type(&mut p.ty, &scope);
+            }
+            rewrite_type(&mut f.ret, &scope);
+            let mut locals = f.params.iter().map(|p| p.name.name.clone()).collect();
+            rewrite_block(&mut f.body, &scope, &mut locals);
+        }
+        for p in &mut prog.protocols {
+            p.name.name = qualify(&prefix, &p.name.name);
+        }
+        self.modules[slot] = Some(prog);
+    }
+}
+
+fn qualify(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{prefix}::{name}")
+    }
+}
+
+/// Name resolution context of one module.
+struct Scope<'a> {
+    prefix: &'a str,
+    local_items: HashSet<String>, // functions, structs, enums, type aliases and constants
+    /// Constants are read as plain names, so unqualified reads are resolved too.
+    consts: HashSet<String>,
+    aliases: HashMap<String, String>,
+}
+
+impl Scope<'_> {
+    /// `derive` -> `keys::derive` for a local item of module `keys`;
+    /// `keys::derive` -> `<prefix of keys>::derive` for an imported module;
+    /// `Outcome::Ok` -> `keys::Outcome::Ok` for a variant of a local enum.
+    /// Anything else (intrinsics, unknown names) is left for the typechecker.
+    fn resolve(&self, name: &mut String) {
+        if let Some((head, rest)) = name.split_once("::") {
+            if let Some(p) = self.aliases.get(head) {
+                *name = format!("{p}::{rest}");
+            } else if self.local_items.contains(head) {
+                *name = qualify(self.prefix, name);
+            }
+        } else if self.local_items.contains(name.as_str()) {
+            *name = qualify(self.prefix, name);
+        }
+    }
+}
+
+/// `locals` are the parameters and bindings in scope; they shadow module constants.
+fn rewrite_block(b: &mut Block, sc: &Scope, locals: &mut Vec<String>) {
+    let outer = locals.len();
+    for s in &mut b.stmts {
+        rewrite_stmt(s, sc, locals);
+    }
+    locals.truncate(outer);
+}
+
+fn rewrite_type(t: &mut Type, sc: &Scope) {
+    match t {
+        Type::Named { name, .. } => sc.resolve(name),
+        Type::Secret { inner, .. } | Type::Labeled { inner, .. } => rewrite_type(inner, sc),
+
+        Type::Bytes { len, .. } => rewrite_expr(len, sc, &[]),
+        Type::Int { .. }
+        | Type::Bool { .. }
+        | Type::Unit { .. }
+        | Type::Str { .. }
+        | Type::Error { .. } => {}
+    }
+}
+
+fn rewrite_stmt(s: &mut Stmt, sc: &Scope, locals: &mut Vec<String>) {
+    match s {
+        Stmt::Let { name, ty, expr, .. } => {
+            if let Some(t) = ty {
+                rewrite_type(t, sc);
+            }
+            rewrite_expr(expr, sc, locals);
+            locals.push(name.name.clone());
+        }
+        Stmt::Assign { expr, .. } | Stmt::Expr { expr, .. } => rewrite_expr(expr, sc, locals),
+        Stmt::Return { expr, .. } => {
+            if let Some(e) = expr {
+                rewrite_expr(e, sc, locals);
+            }
+        }
+        Stmt::If {
+            cond,
+            then_blk,
+            else_blk,
+            ..
+        } => {
+            rewrite_expr(cond, sc, locals);
+            rewrite_block(then_blk, sc, locals);
+            if let Some(eb) = else_blk {
+                rewrite_block(eb, sc, locals);
+            }
+        }
+        Stmt::For {
+            var,
+            start,
+            end,
+            body,
+            ..
+        } => {
+            rewrite_expr(start, sc, locals);
+            rewrite_expr(end, sc, locals);
+            locals.push(var.name.clone());
+            rewrite_block(body, sc, locals);
+            locals.pop();
+        }
+        Stmt::Match {
+            scrutinee, arms, ..
+        } => {
+            rewrite_expr(scrutinee, sc, locals);
+            for arm in arms {
+                let outer = locals.len();
+                if let Pattern::Variant { path, bindings, .. } = &mut arm.pattern {
+                    sc.resolve(&mut path.name);
+                    locals.extend(bindings.iter().map(|b| b.name.clone()));
+                }
+                rewrite_block(&mut arm.body, sc, locals);
+                locals.truncate(outer);
+            }
+        }
+        Stmt::Error { .. } => {}
+    }
+}
+
+fn rewrite_expr(e: &mut Expr, sc: &Scope, locals: &[String]) {
+    match e {
+        Expr::Call { callee, args, .. } => {
+            sc.resolve(&mut callee.name);
+            for a in args {
+                rewrite_expr(a, sc, locals);
+            }
+        }
+        // A variable names an item only when qualified, or when it is a constant of this
+        // module that no local binding shadows.
+        Expr::Var { name, .. }
+            if name.name.contains("::")
+                || (sc.consts.contains(&name.name) && !locals.contains(&name.name)) =>
+        {
+            sc.resolve(&mut name.name)
+        }
+        Expr::Field { base, .. } => rewrite_expr(base, sc, locals),
+        Expr::Index { base, index, .. } => {
+            rewrite_expr(base, sc, locals);
+            rewrite_expr(index, sc, locals);
+        }
+        Expr::Slice {
+            base, start, end, ..
+        } => {
+            rewrite_expr(base, sc, locals);
+            rewrite_expr(start, sc, locals);
+            rewrite_expr(end, sc, locals);
+        }
+        Expr::ArrayLit { elems, .. } => {
+            for e in elems {
+                rewrite_expr(e, sc, locals);
+            }
+        }
+        Expr::ArrayRepeat { elem, count, .. } => {
+            rewrite_expr(elem, sc, locals);
+            rewrite_expr(count, sc, locals);
+        }
+        Expr::StructLit { name, fields, .. } => {
+            sc.resolve(&mut name.name);
+            for f in fields {
+                rewrite_expr(&mut f.expr, sc, locals);
+            }
+        }
+        Expr::Unary { expr, .. } => rewrite_expr(expr, sc, locals),
+        Expr::Binary { lhs, rhs, .. } => {
+            rewrite_expr(lhs, sc, locals);
+            rewrite_expr(rhs, sc, locals);
+        }
+        Expr::IntLit { .. }
+        | Expr::BoolLit { .. }
+        | Expr::StrLit { .. }
+        | Expr::Var { .. }
+        | Expr::Move { .. }
+        | Expr::Error { .. } => {}
+    }
+}
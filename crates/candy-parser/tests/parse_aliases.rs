@@ -0,0 +1,11 @@
+use candy_parser::parse_file;
+
+#[test]
+fn parse_type_alias() {
+    let src = "/// 32-byte key\ntype Key = secret Bytes[32];\nfn main() -> Unit { return; }";
+    let p = parse_file("main.candy", src).unwrap();
+    assert_eq!(p.aliases.len(), 1);
+    assert_eq!(p.aliases[0].name.name, "Key");
+    assert_eq!(p.aliases[0].docs, vec!["32-byte key"]);
+    assert!(matches!(p.aliases[0].ty, candy_ast::Type::Secret { .. }));
+}
@@ -0,0 +1,41 @@
+use candy_ast::{Expr, Stmt};
+use candy_parser::{parse_file, parse_file_with_report};
+
+#[test]
+fn parse_let_mut_and_assignment() {
+    let src = r#"
+fn main() -> Unit {
+  let mut n: Int = 0;
+  n = n + 1;
+  return;
+}
+"#;
+    let p = parse_file("main.candy", src).expect("parse ok");
+    let stmts = &p.funcs[0].body.stmts;
+    assert!(matches!(&stmts[0], Stmt::Let { mutable: true, .. }));
+    let Stmt::Assign { name, expr, .. } = &stmts[1] else {
+        panic!("expected assignment");
+    };
+    assert_eq!(name.name, "n");
+    assert!(matches!(expr, Expr::Binary { .. }));
+}
+
+#[test]
+fn plain_let_is_immutable() {
+    let p = parse_file(
+        "main.candy",
+        "fn main() -> Unit { let n: Int = 0; return; }",
+    )
+    .expect("parse ok");
+    assert!(matches!(
+        &p.funcs[0].body.stmts[0],
+        Stmt::Let { mutable: false, .. }
+    ));
+}
+
+#[test]
+fn assignment_to_non_variable_is_error() {
+    let (_, r) = parse_file_with_report("main.candy", "fn main() -> Unit { 1 = 2; return; }");
+    let codes: Vec<_> = r.diagnostics.iter().map(|d| d.code.as_str()).collect();
+    assert_eq!(codes, vec!["parse-invalid-assign-target"]);
+}
@@ -0,0 +1,26 @@
+use candy_parser::parse_file;
+
+#[test]
+fn parse_lint_attributes() {
+    let src = "/// Helper\n#[allow(unused-variable, unused-parameter)]\n#[deny(duplicate-effect)]\nfn f(a: Int) -> Unit { return; }";
+    let p = parse_file("main.candy", src).unwrap();
+    let f = &p.funcs[0];
+    assert_eq!(f.docs, vec!["Helper"]);
+    assert_eq!(f.attrs.len(), 2);
+    assert_eq!(f.attrs[0].level, candy_ast::LintLevel::Allow);
+    let rules: Vec<&str> = f.attrs[0].rules.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(rules, vec!["unused-variable", "unused-parameter"]);
+    assert_eq!(f.attrs[1].level, candy_ast::LintLevel::Deny);
+}
+
+#[test]
+fn bad_attributes_are_reported() {
+    let err = parse_file("main.candy", "#[inline]\nfn f() -> Unit { return; }").unwrap_err();
+    assert_eq!(err.diagnostics[0].code, "parse-unknown-attribute");
+    let err = parse_file(
+        "main.candy",
+        "#[allow(unused-variable)]\nstruct S { a: Int }",
+    )
+    .unwrap_err();
+    assert_eq!(err.diagnostics[0].code, "parse-attribute-target");
+}
@@ -0,0 +1,56 @@
+use candy_ast::{Expr, Stmt, Type};
+use candy_parser::{parse_file, parse_file_with_report};
+
+fn let_parts(s: &Stmt) -> (&Option<Type>, &Expr) {
+    let Stmt::Let { ty, expr, .. } = s else {
+        panic!("expected let");
+    };
+    (ty, expr)
+}
+
+#[test]
+fn parse_bytes_types_literals_index_and_slice() {
+    let src = r#"
+fn main() -> Unit {
+  let k: secret Bytes[4] = [0x00, 0x01, 2, 3];
+  let z: Bytes[32] = [0; 32];
+  let b: Int = z[1];
+  let h: Bytes[2] = z[0..2];
+  return;
+}
+"#;
+    let p = parse_file("main.candy", src).expect("parse ok");
+    let stmts = &p.funcs[0].body.stmts;
+
+    let (ty, expr) = let_parts(&stmts[0]);
+    let Some(Type::Secret { inner, .. }) = ty else {
+        panic!("expected secret type");
+    };
+    assert!(
+        matches!(&**inner, Type::Bytes { len, .. } if matches!(**len, Expr::IntLit { value: 4, .. }))
+    );
+    assert!(matches!(expr, Expr::ArrayLit { elems, .. } if elems.len() == 4));
+
+    let (_, expr) = let_parts(&stmts[1]);
+    assert!(matches!(expr, Expr::ArrayRepeat { .. }));
+
+    let (_, expr) = let_parts(&stmts[2]);
+    assert!(matches!(expr, Expr::Index { .. }));
+
+    let (_, expr) = let_parts(&stmts[3]);
+    let Expr::Slice { start, end, .. } = expr else {
+        panic!("expected slice");
+    };
+    assert!(matches!(**start, Expr::IntLit { value: 0, .. }));
+    assert!(matches!(**end, Expr::IntLit { value: 2, .. }));
+}
+
+#[test]
+fn bytes_without_length_is_reported() {
+    let (_, r) = parse_file_with_report(
+        "main.candy",
+        "fn f(k: Bytes) -> Unit { return; } fn main() -> Unit { return; }",
+    );
+    let codes: Vec<_> = r.diagnostics.iter().map(|d| d.code.as_str()).collect();
+    assert_eq!(codes, vec!["parse-expected-lbracket"]);
+}
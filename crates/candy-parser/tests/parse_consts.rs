@@ -0,0 +1,17 @@
+use candy_parser::parse_file;
+
+#[test]
+fn parse_const() {
+    let src = "/// Round count\nconst ROUNDS: Int = 2 * 5;\nfn main() -> Unit { return; }";
+    let p = parse_file("main.candy", src).unwrap();
+    assert_eq!(p.consts.len(), 1);
+    assert_eq!(p.consts[0].name.name, "ROUNDS");
+    assert_eq!(p.consts[0].docs, vec!["Round count"]);
+    assert!(matches!(p.consts[0].expr, candy_ast::Expr::Binary { .. }));
+}
+
+#[test]
+fn const_without_type_is_reported() {
+    let err = parse_file("main.candy", "const N = 1;").unwrap_err();
+    assert_eq!(err.diagnostics[0].code, "parse-expected-colon");
+}
@@ -0,0 +1,13 @@
+use candy_parser::parse_file;
+
+#[test]
+fn parse_ct_marker() {
+    let src = "fn cmp(a: secret Int, b: secret Int) -> secret Bool ct { let ct: Int = 1; return; }";
+    let p = parse_file("main.candy", src).unwrap();
+    assert!(p.funcs[0].ct);
+    assert!(
+        matches!(&p.funcs[0].body.stmts[0], candy_ast::Stmt::Let { name, .. } if name.name == "ct")
+    );
+    let p = parse_file("main.candy", "fn f() -> Unit effects(io) { return; }").unwrap();
+    assert!(!p.funcs[0].ct);
+}
@@ -0,0 +1,54 @@
+use candy_parser::parse_file;
+
+#[test]
+fn doc_comments_attach_to_fn() {
+    let src = r#"
+/// Entry point.
+/// Second line.
+fn main() -> Unit {
+  // plain comment, not a doc
+  return; /* trailing */
+}
+"#;
+    let p = parse_file("main.candy", src).expect("parse ok");
+    assert_eq!(p.funcs[0].docs, vec!["Entry point.", "Second line."]);
+}
+
+#[test]
+fn doc_comments_attach_to_protocol_items() {
+    let src = r#"
+/// Handshake channel.
+protocol Channel {
+  /// Before any message.
+  state Init;
+  /// Terminal state.
+  final state Done;
+  /// Peer acknowledged the hello.
+  transition Init -> Done;
+}
+fn main() -> Unit { return; }
+"#;
+    let p = parse_file("main.candy", src).expect("parse ok");
+    let proto = &p.protocols[0];
+    assert_eq!(proto.docs, vec!["Handshake channel."]);
+    assert_eq!(proto.states[0].docs, vec!["Before any message."]);
+    assert_eq!(proto.states[1].docs, vec!["Terminal state."]);
+    assert_eq!(
+        proto.transitions[0].docs,
+        vec!["Peer acknowledged the hello."]
+    );
+    assert!(p.funcs[0].docs.is_empty());
+}
+
+#[test]
+fn stray_doc_comment_does_not_leak_to_next_item() {
+    let src = r#"
+fn a() -> Unit {
+  /// misplaced
+  return;
+}
+fn main() -> Unit { return; }
+"#;
+    let p = parse_file("main.candy", src).expect("parse ok");
+    assert!(p.funcs.iter().all(|f| f.docs.is_empty()));
+}
@@ -0,0 +1,39 @@
+use candy_ast::{Expr, Stmt};
+use candy_parser::parse_file;
+
+#[test]
+fn parse_for_range_loop() {
+    let src = r#"
+fn main() -> Unit {
+  for i in 0..10 {
+    log("round");
+  }
+  return;
+}
+"#;
+    let p = parse_file("main.candy", src).expect("parse ok");
+    let Stmt::For {
+        var,
+        start,
+        end,
+        body,
+        ..
+    } = &p.funcs[0].body.stmts[0]
+    else {
+        panic!("expected for loop");
+    };
+    assert_eq!(var.name, "i");
+    assert!(matches!(start, Expr::IntLit { value: 0, .. }));
+    assert!(matches!(end, Expr::IntLit { value: 10, .. }));
+    assert_eq!(body.stmts.len(), 1);
+}
+
+#[test]
+fn missing_range_is_reported() {
+    let err = parse_file(
+        "main.candy",
+        "fn main() -> Unit { for i in 10 { } return; }",
+    )
+    .unwrap_err();
+    assert_eq!(err.diagnostics[0].code, "parse-expected-range");
+}
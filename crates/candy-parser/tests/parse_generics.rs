@@ -0,0 +1,18 @@
+use candy_parser::parse_file;
+
+#[test]
+fn parse_generic_params_with_bounds() {
+    let src = "fn f<T, U: Copy>(a: T, b: secret U) -> T { return a; }";
+    let p = parse_file("main.candy", src).unwrap();
+    let g = &p.funcs[0].generics;
+    assert_eq!(g.len(), 2);
+    assert_eq!(g[0].name.name, "T");
+    assert!(g[0].bounds.is_empty());
+    assert_eq!(g[1].bounds[0].name, "Copy");
+}
+
+#[test]
+fn unclosed_generic_list_is_reported() {
+    let err = parse_file("main.candy", "fn f<T(a: T) -> Unit { return; }").unwrap_err();
+    assert_eq!(err.diagnostics[0].code, "parse-expected-gt");
+}
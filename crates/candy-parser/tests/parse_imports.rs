@@ -0,0 +1,327 @@
+use std::fs;
+use std::path::Path;
+
+use candy_ast::{Expr, Stmt};
+use candy_parser::{parse_file, parse_file_with_imports};
+
+fn write(dir: &Path, rel: &str, src: &str) -> String {
+    let p = dir.join(rel);
+    fs::create_dir_all(p.parent().unwrap()).unwrap();
+    fs::write(&p, src).unwrap();
+    p.to_string_lossy().into_owned()
+}
+
+fn load(root: &str) -> (candy_ast::Program, Vec<String>) {
+    let src = fs::read_to_string(root).unwrap();
+    let (p, r) = parse_file_with_imports(root, &src);
+    (p, r.diagnostics.into_iter().map(|d| d.code).collect())
+}
+
+fn first_call(stmts: &[Stmt]) -> String {
+    for s in stmts {
+        if let Stmt::Let { expr, .. } | Stmt::Expr { expr, .. } = s {
+            if let Expr::Call { callee, .. } = expr {
+                return callee.name.clone();
+            }
+        }
+    }
+    panic!("no call");
+}
+
+#[test]
+fn parses_import_and_mod_declarations() {
+    let src = r#"
+import "crypto/keys.candy";
+mod util;
+fn main() -> Unit { keys::derive(1); return; }
+"#;
+    let p = parse_file("main.candy", src).expect("parse ok");
+    assert_eq!(p.imports.len(), 2);
+    assert_eq!(p.imports[0].path, "crypto/keys.candy");
+    assert_eq!(p.imports[0].name.name, "keys");
+    assert_eq!(p.imports[1].path, "util.candy");
+    assert_eq!(p.imports[1].name.name, "util");
+    assert_eq!(first_call(&p.funcs[0].body.stmts), "keys::derive");
+}
+
+#[test]
+fn invalid_import_path_is_reported() {
+    let err = parse_file("main.candy", r#"import "keys.txt";"#).unwrap_err();
+    assert_eq!(err.diagnostics[0].code, "import-invalid-path");
+}
+
+#[test]
+fn resolves_modules_relative_to_importer() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = write(
+        dir.path(),
+        "main.candy",
+        r#"
+import "crypto/keys.candy";
+fn main() -> Unit { let k: Int = keys::derive(1); return; }
+"#,
+    );
+    write(
+        dir.path(),
+        "crypto/keys.candy",
+        r#"
+mod util;
+fn derive(x: Int) -> Int { let y: Int = helper(); return util::mix(x); }
+fn helper() -> Int { return 1; }
+"#,
+    );
+    write(
+        dir.path(),
+        "crypto/util.candy",
+        "fn mix(x: Int) -> Int { return x; }",
+    );
+
+    let (p, codes) = load(&root);
+    assert!(codes.is_empty(), "{codes:?}");
+
+    let names: Vec<&str> = p.funcs.iter().map(|f| f.name.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec!["main", "keys::derive", "keys::helper", "keys::util::mix"]
+    );
+
+    let derive = &p.funcs[1];
+    assert_eq!(first_call(&derive.body.stmts), "keys::helper");
+    let Stmt::Return {
+        expr: Some(Expr::Call { callee, .. }),
+        ..
+    } = &derive.body.stmts[1]
+    else {
+        panic!("expected return of call");
+    };
+    assert_eq!(callee.name, "keys::util::mix");
+
+    // Spans point at the file each item came from.
+    assert!(derive.span.file.ends_with("keys.candy"));
+    assert!(p.funcs[3].span.file.ends_with("util.candy"));
+}
+
+#[test]
+fn import_cycle_is_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = write(
+        dir.path(),
+        "main.candy",
+        "mod a;\nfn main() -> Unit { return; }",
+    );
+    write(dir.path(), "a.candy", "mod b;\nfn fa() -> Unit { return; }");
+    write(dir.path(), "b.candy", "mod a;\nfn fb() -> Unit { return; }");
+
+    let src = fs::read_to_string(&root).unwrap();
+    let (_, r) = parse_file_with_imports(&root, &src);
+    assert_eq!(r.diagnostics.len(), 1);
+    let d = &r.diagnostics[0];
+    assert_eq!(d.code, "import-cycle");
+    assert!(d.span.file.ends_with("b.candy"));
+    assert!(d.message.contains("a.candy -> "), "{}", d.message);
+}
+
+#[test]
+fn shared_module_is_loaded_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = write(
+        dir.path(),
+        "main.candy",
+        "mod a;\nmod b;\nfn main() -> Unit { return; }",
+    );
+    write(
+        dir.path(),
+        "a.candy",
+        "mod c;\nfn fa() -> Int { return c::one(); }",
+    );
+    write(
+        dir.path(),
+        "b.candy",
+        "mod c;\nfn fb() -> Int { return c::one(); }",
+    );
+    write(dir.path(), "c.candy", "fn one() -> Int { return 1; }");
+
+    let (p, codes) = load(&root);
+    assert!(codes.is_empty(), "{codes:?}");
+    assert_eq!(
+        p.funcs
+            .iter()
+            .filter(|f| f.name.name.ends_with("one"))
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn missing_import_is_reported_at_import_site() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = write(
+        dir.path(),
+        "main.candy",
+        "fn main() -> Unit { return; }\nmod nope;",
+    );
+    let src = fs::read_to_string(&root).unwrap();
+    let (_, r) = parse_file_with_imports(&root, &src);
+    assert_eq!(r.diagnostics[0].code, "import-not-found");
+    assert_eq!(r.diagnostics[0].span.start_line, 2);
+}
+
+#[test]
+fn imported_struct_types_are_qualified() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = write(
+        dir.path(),
+        "main.candy",
+        r#"
+mod keys;
+fn main() -> Unit { let kp: keys::Pair = keys::Pair { a: 1 }; return; }
+"#,
+    );
+    write(
+        dir.path(),
+        "keys.candy",
+        r#"
+struct Pair { a: Int }
+fn make() -> Pair { return Pair { a: 2 }; }
+"#,
+    );
+    let (p, codes) = load(&root);
+    assert!(codes.is_empty(), "{codes:?}");
+    assert_eq!(p.structs[0].name.name, "keys::Pair");
+    let make = p
+        .funcs
+        .iter()
+        .find(|f| f.name.name == "keys::make")
+        .unwrap();
+    assert!(matches!(&make.ret, candy_ast::Type::Named { name, .. } if name == "keys::Pair"));
+    let Stmt::Let {
+        ty: Some(candy_ast::Type::Named { name, .. }),
+        expr: Expr::StructLit { name: lit, .. },
+        ..
+    } = &p.funcs[0].body.stmts[0]
+    else {
+        panic!("expected struct let");
+    };
+    assert_eq!(name, "keys::Pair");
+    assert_eq!(lit.name, "keys::Pair");
+}
+
+#[test]
+fn imported_type_aliases_are_qualified() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = write(
+        dir.path(),
+        "main.candy",
+        "mod keys;\nfn main() -> Unit { let k: keys::Key = 1; return; }",
+    );
+    write(
+        dir.path(),
+        "keys.candy",
+        "struct Pair { a: Int }\ntype Key = secret Int;\ntype P = Pair;",
+    );
+    let (p, codes) = load(&root);
+    assert!(codes.is_empty(), "{codes:?}");
+    let names: Vec<&str> = p.aliases.iter().map(|a| a.name.name.as_str()).collect();
+    assert_eq!(names, vec!["keys::Key", "keys::P"]);
+    assert!(
+        matches!(&p.aliases[1].ty, candy_ast::Type::Named { name, .. } if name == "keys::Pair")
+    );
+    let Stmt::Let {
+        ty: Some(candy_ast::Type::Named { name, .. }),
+        ..
+    } = &p.funcs[0].body.stmts[0]
+    else {
+        panic!("expected annotated let");
+    };
+    assert_eq!(name, "keys::Key");
+}
+
+#[test]
+fn imported_consts_are_qualified() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = write(
+        dir.path(),
+        "main.candy",
+        "mod sizes;\nfn main() -> Unit { let k: Bytes[sizes::KEY] = [0; 32]; return; }",
+    );
+    write(
+        dir.path(),
+        "sizes.candy",
+        "const HALF: Int = 16;\nconst KEY: Int = HALF * 2;",
+    );
+    let (p, codes) = load(&root);
+    assert!(codes.is_empty(), "{codes:?}");
+    let names: Vec<&str> = p.consts.iter().map(|c| c.name.name.as_str()).collect();
+    assert_eq!(names, vec!["sizes::HALF", "sizes::KEY"]);
+    let candy_ast::Expr::Binary { lhs, .. } = &p.consts[1].expr else {
+        panic!("expected binary initializer");
+    };
+    assert!(matches!(&**lhs, candy_ast::Expr::Var { name, .. } if name.name == "sizes::HALF"));
+}
+
+#[test]
+fn imported_labels_are_not_qualified() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = write(
+        dir.path(),
+        "main.candy",
+        "mod tenants;\nfn main() -> Unit { return; }",
+    );
+    write(
+        dir.path(),
+        "tenants.candy",
+        "label public < tenant < secret;\nfn f(x: labeled<tenant> Int) -> Unit { return; }",
+    );
+    let (p, codes) = load(&root);
+    assert!(codes.is_empty(), "{codes:?}");
+    assert_eq!(p.labels[0].names[1].name, "tenant");
+    assert!(
+        matches!(&p.funcs[1].params[0].ty, candy_ast::Type::Labeled { label, .. } if label.name == "tenant")
+    );
+}
+
+#[test]
+fn locals_shadow_imported_consts() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = write(
+        dir.path(),
+        "main.candy",
+        "mod keys;\nfn main() -> Unit { return; }",
+    );
+    write(
+        dir.path(),
+        "keys.candy",
+        r#"const ROUNDS: Int = 4;
+fn derive(x: Int) -> Int { let ROUNDS: Int = x; return ROUNDS; }
+fn param(ROUNDS: Int) -> Int { return ROUNDS; }
+fn looped() -> Int { for ROUNDS in 0..2 { let y: Int = ROUNDS; } return ROUNDS; }
+"#,
+    );
+    let (p, codes) = load(&root);
+    assert!(codes.is_empty(), "{codes:?}");
+    let read = |f: &str, i: usize| -> String {
+        let f = p.funcs.iter().find(|g| g.name.name == f).unwrap();
+        match &f.body.stmts[i] {
+            Stmt::Return {
+                expr: Some(Expr::Var { name, .. }),
+                ..
+            }
+            | Stmt::Let {
+                expr: Expr::Var { name, .. },
+                ..
+            } => name.name.clone(),
+            Stmt::For { body, .. } => match &body.stmts[0] {
+                Stmt::Let {
+                    expr: Expr::Var { name, .. },
+                    ..
+                } => name.name.clone(),
+                s => panic!("unexpected {s:?}"),
+            },
+            s => panic!("unexpected {s:?}"),
+        }
+    };
+    assert_eq!(read("keys::derive", 1), "ROUNDS");
+    assert_eq!(read("keys::param", 0), "ROUNDS");
+    assert_eq!(read("keys::looped", 0), "ROUNDS");
+    assert_eq!(read("keys::looped", 1), "keys::ROUNDS");
+}
@@ -0,0 +1,22 @@
+use candy_parser::parse_file;
+
+#[test]
+fn parse_label_order_and_labeled_type() {
+    let src = "/// Data classes\nlabel public < internal < secret;\nfn f(label: labeled<internal> Int) -> labeled<secret> Int { return label; }";
+    let p = parse_file("main.candy", src).unwrap();
+    let names: Vec<&str> = p.labels[0].names.iter().map(|n| n.name.as_str()).collect();
+    assert_eq!(names, vec!["public", "internal", "secret"]);
+    assert_eq!(p.labels[0].docs, vec!["Data classes"]);
+    assert!(
+        matches!(&p.funcs[0].params[0].ty, candy_ast::Type::Labeled { label, .. } if label.name == "internal")
+    );
+    assert!(
+        matches!(&p.funcs[0].ret, candy_ast::Type::Labeled { label, .. } if label.name == "secret")
+    );
+}
+
+#[test]
+fn single_label_order_is_reported() {
+    let err = parse_file("main.candy", "label public;").unwrap_err();
+    assert_eq!(err.diagnostics[0].code, "parse-expected-lt");
+}
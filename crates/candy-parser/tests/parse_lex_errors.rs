@@ -0,0 +1,21 @@
+use candy_parser::parse_file;
+
+#[test]
+fn lexical_errors_are_merged_into_parse_report() {
+    let src = "fn main() -> Unit {\n  let x: Int = 99999999999999999999;\n  return;\n}\n";
+    let err = parse_file("main.candy", src).unwrap_err();
+
+    let d0 = &err.diagnostics[0];
+    assert_eq!(d0.code, "lex-int-overflow");
+    assert_eq!(d0.span.file, "main.candy");
+    assert_eq!((d0.span.start_line, d0.span.start_col), (2, 16));
+    assert_eq!(err.diagnostics.len(), 1, "{:?}", err.diagnostics);
+}
+
+#[test]
+fn unknown_char_does_not_derail_parsing() {
+    let src = "fn main() -> Unit { @ return; }";
+    let err = parse_file("main.candy", src).unwrap_err();
+    let codes: Vec<&str> = err.diagnostics.iter().map(|d| d.code.as_str()).collect();
+    assert_eq!(codes, vec!["lex-unknown-char"]);
+}
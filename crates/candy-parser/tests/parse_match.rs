@@ -0,0 +1,79 @@
+use candy_ast::{Expr, Pattern, Stmt, Type};
+use candy_parser::{parse_file, parse_file_with_report};
+
+#[test]
+fn parse_enum_declaration() {
+    let src = r#"
+/// Result of an authorization check.
+enum Outcome { Ok(Int), Denied, Pair(Int, secret Int), }
+fn main() -> Unit { return; }
+"#;
+    let p = parse_file("main.candy", src).expect("parse ok");
+    let e = &p.enums[0];
+    assert_eq!(e.name.name, "Outcome");
+    assert_eq!(e.docs, vec!["Result of an authorization check."]);
+    let names: Vec<_> = e.variants.iter().map(|v| v.name.name.as_str()).collect();
+    assert_eq!(names, vec!["Ok", "Denied", "Pair"]);
+    assert!(matches!(e.variants[0].payload[..], [Type::Int { .. }]));
+    assert!(e.variants[1].payload.is_empty());
+    assert!(matches!(e.variants[2].payload[1], Type::Secret { .. }));
+}
+
+#[test]
+fn parse_match_statement() {
+    let src = r#"
+fn main() -> Unit {
+  let o: Outcome = Outcome::Ok(1);
+  match o {
+    Outcome::Ok(n) => { log("ok"); },
+    Outcome::Denied => { }
+    _ => { }
+  }
+  return;
+}
+"#;
+    let p = parse_file("main.candy", src).expect("parse ok");
+    let stmts = &p.funcs[0].body.stmts;
+    let Stmt::Let {
+        expr: Expr::Call { callee, .. },
+        ..
+    } = &stmts[0]
+    else {
+        panic!("expected variant constructor call");
+    };
+    assert_eq!(callee.name, "Outcome::Ok");
+
+    let Stmt::Match {
+        scrutinee, arms, ..
+    } = &stmts[1]
+    else {
+        panic!("expected match");
+    };
+    assert!(matches!(scrutinee, Expr::Var { name, .. } if name.name == "o"));
+    assert_eq!(arms.len(), 3);
+    let Pattern::Variant { path, bindings, .. } = &arms[0].pattern else {
+        panic!("expected variant pattern");
+    };
+    assert_eq!(path.name, "Outcome::Ok");
+    assert_eq!(bindings[0].name, "n");
+    assert_eq!(arms[0].body.stmts.len(), 1);
+    assert!(matches!(&arms[1].pattern, Pattern::Variant { bindings, .. } if bindings.is_empty()));
+    assert!(matches!(arms[2].pattern, Pattern::Wildcard { .. }));
+}
+
+#[test]
+fn missing_fat_arrow_recovers_at_arm_body() {
+    let src = r#"
+fn main() -> Unit {
+  match b { true { } false => { } }
+  return;
+}
+"#;
+    let (p, r) = parse_file_with_report("main.candy", src);
+    let codes: Vec<_> = r.diagnostics.iter().map(|d| d.code.as_str()).collect();
+    assert_eq!(codes, vec!["parse-expected-fat-arrow"]);
+    let Stmt::Match { arms, .. } = &p.funcs[0].body.stmts[0] else {
+        panic!("expected match");
+    };
+    assert_eq!(arms.len(), 2);
+}
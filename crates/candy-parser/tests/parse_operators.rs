@@ -0,0 +1,67 @@
+use candy_ast::{BinOp, Expr, Stmt, UnOp};
+use candy_parser::parse_file;
+
+fn let_expr(src_expr: &str) -> Expr {
+    let src = format!("fn main() -> Unit {{ let x = {src_expr}; return; }}");
+    let p = parse_file("main.candy", &src).expect("parse ok");
+    match &p.funcs[0].body.stmts[0] {
+        Stmt::Let { expr, .. } => expr.clone(),
+        s => panic!("expected let, got {s:?}"),
+    }
+}
+
+/// Renders the tree with explicit parentheses.
+fn show(e: &Expr) -> String {
+    match e {
+        Expr::IntLit { value, .. } => value.to_string(),
+        Expr::BoolLit { value, .. } => value.to_string(),
+        Expr::Var { name, .. } => name.name.clone(),
+        Expr::Unary { op, expr, .. } => format!("({}{})", op.as_str(), show(expr)),
+        Expr::Binary { op, lhs, rhs, .. } => {
+            format!("({} {} {})", show(lhs), op.as_str(), show(rhs))
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+}
+
+#[test]
+fn arithmetic_precedence_and_associativity() {
+    assert_eq!(show(&let_expr("1 + 2 * 3")), "(1 + (2 * 3))");
+    assert_eq!(show(&let_expr("a - b - c")), "((a - b) - c)");
+    assert_eq!(show(&let_expr("(1 + 2) * 3")), "((1 + 2) * 3)");
+    assert_eq!(show(&let_expr("a % b / c")), "((a % b) / c)");
+}
+
+#[test]
+fn logical_and_comparison_precedence() {
+    assert_eq!(
+        show(&let_expr("a < b && !c || d == 1 + 2")),
+        "(((a < b) && (!c)) || (d == (1 + 2)))"
+    );
+}
+
+#[test]
+fn unary_minus_binds_tighter_than_binary() {
+    let e = let_expr("-a * b");
+    assert_eq!(show(&e), "((-a) * b)");
+    let Expr::Binary { op, lhs, .. } = e else {
+        panic!()
+    };
+    assert_eq!(op, BinOp::Mul);
+    assert!(matches!(*lhs, Expr::Unary { op: UnOp::Neg, .. }));
+}
+
+#[test]
+fn binary_span_covers_both_operands() {
+    let e = let_expr("a + bb");
+    let sp = e.span();
+    // `fn main() -> Unit { let x = a + bb; ...`: `a` at col 29, `bb` ends at col 35.
+    assert_eq!((sp.start_col, sp.end_col), (29, 35));
+}
+
+#[test]
+fn chained_comparison_is_rejected() {
+    let src = "fn main() -> Unit { let x = 1 < 2 < 3; return; }";
+    let err = parse_file("main.candy", src).unwrap_err();
+    assert_eq!(err.diagnostics[0].code, "parse-chained-comparison");
+}
@@ -0,0 +1,13 @@
+use candy_parser::parse_file;
+
+#[test]
+fn parse_multiple_params_with_trailing_comma() {
+    let src = "fn f(a: Int, b: secret Bool,) -> Int { return a; }";
+    let p = parse_file("main.candy", src).unwrap();
+    let names: Vec<&str> = p.funcs[0]
+        .params
+        .iter()
+        .map(|p| p.name.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["a", "b"]);
+}
@@ -0,0 +1,92 @@
+use candy_ast::{Expr, Stmt};
+use candy_parser::{parse_file, parse_file_with_report};
+
+fn codes(src: &str) -> Vec<String> {
+    let (_, report) = parse_file_with_report("main.candy", src);
+    report.diagnostics.into_iter().map(|d| d.code).collect()
+}
+
+#[test]
+fn reports_errors_in_several_functions_at_once() {
+    let src = r#"
+fn a() -> Unit {
+  let x = ;
+  return;
+}
+fn b() -> Unit {
+  ) ;
+  return;
+}
+fn main() -> Unit { return; }
+"#;
+    let (prog, report) = parse_file_with_report("main.candy", src);
+    let c: Vec<&str> = report.diagnostics.iter().map(|d| d.code.as_str()).collect();
+    assert_eq!(c, vec!["parse-unexpected-token", "parse-unexpected-token"]);
+
+    let names: Vec<&str> = prog.funcs.iter().map(|f| f.name.name.as_str()).collect();
+    assert_eq!(names, vec!["a", "b", "main"]);
+
+    // `let x = ;` keeps the binding with an error initializer.
+    assert!(matches!(
+        &prog.funcs[0].body.stmts[0],
+        Stmt::Let {
+            expr: Expr::Error { .. },
+            ..
+        }
+    ));
+    assert!(matches!(&prog.funcs[1].body.stmts[0], Stmt::Error { .. }));
+    // Statements after the broken one are still parsed.
+    assert!(matches!(&prog.funcs[1].body.stmts[1], Stmt::Return { .. }));
+}
+
+#[test]
+fn missing_semicolon_does_not_swallow_next_statement() {
+    let src = "fn main() -> Unit {\n  let x = 1\n  return;\n}\n";
+    let (prog, report) = parse_file_with_report("main.candy", src);
+    assert_eq!(report.diagnostics.len(), 1);
+    assert_eq!(report.diagnostics[0].code, "parse-expected-semi");
+    assert!(matches!(&prog.funcs[0].body.stmts[1], Stmt::Return { .. }));
+}
+
+#[test]
+fn missing_rbrace_stops_at_next_fn() {
+    let src = "fn a() -> Unit {\n  return;\nfn main() -> Unit { return; }\n";
+    let (prog, report) = parse_file_with_report("main.candy", src);
+    assert_eq!(report.diagnostics.len(), 1);
+    assert_eq!(report.diagnostics[0].code, "parse-expected-rbrace");
+    assert_eq!(prog.funcs.len(), 2);
+}
+
+#[test]
+fn junk_at_top_level_is_reported_once() {
+    let c = codes("garbage tokens here 1 2 3\nfn main() -> Unit { return; }");
+    assert_eq!(c, vec!["parse-expected-top-level"]);
+}
+
+#[test]
+fn broken_protocol_item_resyncs() {
+    let src = r#"
+protocol P {
+  state Init;
+  oops oops;
+  final state Done;
+  transition Init -> Done;
+}
+fn main() -> Unit { return; }
+"#;
+    let (prog, report) = parse_file_with_report("main.candy", src);
+    assert_eq!(report.diagnostics.len(), 1);
+    assert_eq!(prog.protocols[0].states.len(), 2);
+    assert_eq!(prog.protocols[0].transitions.len(), 1);
+}
+
+#[test]
+fn missing_type_does_not_eat_the_brace() {
+    let c = codes("fn main() -> { return; }");
+    assert_eq!(c, vec!["parse-expected-type"]);
+}
+
+#[test]
+fn parse_file_still_fails_on_errors() {
+    assert!(parse_file("main.candy", "fn main() -> Unit { let = 1; return; }").is_err());
+}
@@ -0,0 +1,92 @@
+use candy_ast::{Expr, Stmt, Type};
+use candy_parser::{parse_file, parse_file_with_report};
+
+#[test]
+fn parse_struct_declaration() {
+    let src = r#"
+/// Long-term key material.
+struct KeyPair {
+  pub_key: Int,
+  priv_key: secret Int,
+}
+fn main() -> Unit { return; }
+"#;
+    let p = parse_file("main.candy", src).expect("parse ok");
+    let s = &p.structs[0];
+    assert_eq!(s.name.name, "KeyPair");
+    assert_eq!(s.docs, vec!["Long-term key material."]);
+    assert_eq!(s.fields.len(), 2);
+    assert_eq!(s.fields[0].name.name, "pub_key");
+    assert!(matches!(s.fields[1].ty, Type::Secret { .. }));
+}
+
+#[test]
+fn parse_struct_literal_field_access_and_partial_move() {
+    let src = r#"
+fn main() -> Unit {
+  let kp: KeyPair = KeyPair { pub_key: 1, priv_key: 2 };
+  let p: Int = kp.pub_key;
+  let s: secret Int = move(kp.priv_key);
+  return;
+}
+"#;
+    let p = parse_file("main.candy", src).expect("parse ok");
+    let stmts = &p.funcs[0].body.stmts;
+
+    let Stmt::Let {
+        expr: Expr::StructLit { name, fields, .. },
+        ..
+    } = &stmts[0]
+    else {
+        panic!("expected struct literal");
+    };
+    assert_eq!(name.name, "KeyPair");
+    assert_eq!(fields.len(), 2);
+    assert_eq!(fields[1].name.name, "priv_key");
+
+    let Stmt::Let {
+        expr: Expr::Field { base, field, .. },
+        ..
+    } = &stmts[1]
+    else {
+        panic!("expected field access");
+    };
+    assert!(matches!(&**base, Expr::Var { name, .. } if name.name == "kp"));
+    assert_eq!(field.name, "pub_key");
+
+    let Stmt::Let {
+        expr: Expr::Move { name, fields, .. },
+        ..
+    } = &stmts[2]
+    else {
+        panic!("expected move");
+    };
+    assert_eq!(name.name, "kp");
+    assert_eq!(fields[0].name, "priv_key");
+}
+
+#[test]
+fn loop_bound_is_not_a_struct_literal() {
+    let src = r#"
+fn main() -> Unit {
+  let n: Int = 3;
+  for i in 0..n { log("x"); }
+  return;
+}
+"#;
+    let p = parse_file("main.candy", src).expect("parse ok");
+    let Stmt::For { end, body, .. } = &p.funcs[0].body.stmts[1] else {
+        panic!("expected for loop");
+    };
+    assert!(matches!(end, Expr::Var { .. }));
+    assert_eq!(body.stmts.len(), 1);
+}
+
+#[test]
+fn missing_comma_between_fields_is_reported() {
+    let src = "struct P { a: Int b: Int } fn main() -> Unit { return; }";
+    let (p, r) = parse_file_with_report("main.candy", src);
+    let codes: Vec<_> = r.diagnostics.iter().map(|d| d.code.as_str()).collect();
+    assert_eq!(codes, vec!["parse-expected-comma"]);
+    assert_eq!(p.funcs.len(), 1);
+}
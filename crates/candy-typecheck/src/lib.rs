@@ -1,6 +1,10 @@
-use std::collections::{BTreeSet, HashMap};
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
-use candy_ast::{Effect, Expr, FnDecl, Program, Stmt, Type};
+use candy_ast::{
+    BinOp, Block, ConstDecl, Effect, Expr, FnDecl, Ident, MatchArm, Pattern, Program, Stmt, Type,
+    UnOp,
+};
 use candy_diagnostics::{Diagnostic, DiagnosticReport, Span};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -8,331 +12,2411 @@ pub enum Ty {
     Int,
     Bool,
     Unit,
-    Unknown,
+    Str,
+    Struct(String),
+    Enum(String),
+    Bytes(u64), // Bytes[N]
+    /// A type parameter inside its generic function. Unless bounded by `Copy` it may be
+    /// instantiated with a secret, so its values are move-only.
+    Param {
+        name: String,
+        copy: bool,
+    },
+    /// Inference variable, solved by [`Infer`].
+    Var(u32),
+    /// An ill-typed expression that has already been reported. It unifies with
+    /// everything, so one mistake produces one diagnostic.
+    Error,
+}
+
+/// Inference variables of one function body, solved by unification.
+#[derive(Debug, Default)]
+struct Infer {
+    vars: Vec<Option<Ty>>,
+    /// Unannotated `let` bindings, checked for `type-ambiguous` at the end of the body.
+    bindings: Vec<(Ty, Ident)>,
+    /// Expression statements whose value has no type yet (e.g. `mk();` for `fn mk<T>() -> T`),
+    /// checked the same way.
+    dropped: Vec<(Ty, Span)>,
+}
+
+impl Infer {
+    fn fresh(&mut self) -> Ty {
+        self.vars.push(None);
+        Ty::Var(self.vars.len() as u32 - 1)
+    }
+
+    fn resolve(&self, t: &Ty) -> Ty {
+        match t {
+            Ty::Var(v) => match &self.vars[*v as usize] {
+                Some(t) => self.resolve(t),
+                None => t.clone(),
+            },
+            _ => t.clone(),
+        }
+    }
+
+    /// Makes `a` and `b` equal, binding inference variables; false if they cannot be.
+    fn unify(&mut self, a: &Ty, b: &Ty) -> bool {
+        match (self.resolve(a), self.resolve(b)) {
+            (Ty::Var(x), Ty::Var(y)) if x == y => true,
+            // A variable unified with an error becomes an error: it is not ambiguous.
+            (Ty::Var(v), t) | (t, Ty::Var(v)) => {
+                self.vars[v as usize] = Some(t);
+                true
+            }
+            (Ty::Error, _) | (_, Ty::Error) => true,
+            (a, b) => a == b,
+        }
+    }
+}
+
+/// A security label: its rank in the program's `label` order, lowest (public) first.
+/// Every label above the lowest follows the `secret` rules.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+struct Label(usize);
+
+impl Label {
+    const PUBLIC: Label = Label(0);
+
+    fn is_secret(self) -> bool {
+        self > Label::PUBLIC
+    }
+
+    /// The label of a value derived from both.
+    fn join(self, other: Label) -> Label {
+        self.max(other)
+    }
 }
 
 #[derive(Debug, Clone)]
 struct VarInfo {
     ty: Ty,
-    is_secret: bool,
-    moved: bool,
+    label: Label,
+    mutable: bool,
+    moved: Option<Move>, // the `move(x)` that consumed it, if any
+    /// Partial moves: field paths (`move(x.a.b)` -> `[a, b]`) with the move.
+    moved_fields: Vec<(Vec<String>, Move)>,
+    /// The binding of the same name this one hides; it is visible again when the block ends.
+    shadowed: Option<Box<VarInfo>>,
+    /// Where it was declared (`secret-not-consumed` points here).
+    decl: Span,
+    /// The label written in its type annotation; assignments must not exceed it.
+    declared: Option<Label>,
+    /// Bound by a `let` (not a parameter, loop variable or pattern binding).
+    from_let: bool,
 }
 
-fn ty_name(t: &Ty) -> &'static str {
-    match t {
-        Ty::Int => "Int",
-        Ty::Bool => "Bool",
-        Ty::Unit => "Unit",
-        Ty::Unknown => "Unknown",
+/// A `move(...)` out of a variable. `maybe` if only some of the paths reaching this point
+/// went through it.
+#[derive(Debug, Clone)]
+struct Move {
+    span: Span,
+    maybe: bool,
+}
+
+impl VarInfo {
+    fn new(ty: Ty, label: Label, mutable: bool, decl: &Span) -> Self {
+        VarInfo {
+            ty,
+            label,
+            mutable,
+            moved: None,
+            moved_fields: Vec::new(),
+            shadowed: None,
+            decl: decl.clone(),
+            declared: None,
+            from_let: false,
+        }
+    }
+
+    /// Records a move out of the place `path` (empty = the whole variable).
+    fn consume(&mut self, path: Vec<String>, span: &Span) {
+        let m = Move {
+            span: span.clone(),
+            maybe: false,
+        };
+        if path.is_empty() {
+            self.moved = Some(m);
+        } else {
+            self.moved_fields.push((path, m));
+        }
+    }
+
+    /// The move that makes the place `path` (empty = the whole variable) unusable:
+    /// the variable itself, the field or an enclosing field, or a field inside it.
+    fn move_conflict(&self, path: &[String]) -> Option<(&[String], &Move)> {
+        if let Some(m) = &self.moved {
+            return Some((&[], m));
+        }
+        self.moved_fields
+            .iter()
+            .find(|(p, _)| p.starts_with(path) || path.starts_with(p))
+            .map(|(p, m)| (p.as_slice(), m))
+    }
+
+    fn first_move(&self) -> Option<&Span> {
+        self.moved
+            .as_ref()
+            .or(self.moved_fields.first().map(|(_, m)| m))
+            .map(|m| &m.span)
+    }
+
+    /// Moved on every path: the whole variable, or the field `path` (or an enclosing one).
+    fn surely_moved(&self, path: &[String]) -> bool {
+        self.moved.as_ref().is_some_and(|m| !m.maybe)
+            || self
+                .moved_fields
+                .iter()
+                .any(|(p, m)| !m.maybe && path.starts_with(p))
     }
 }
 
-fn is_secret_type(t: &Type) -> bool {
-    matches!(t, Type::Secret { .. })
+/// The state of the variables of `before` where several paths meet again; `paths` are the
+/// states at the end of each path that falls through (diverging paths are left out). A
+/// move made on only some paths becomes a maybe-move. Names declared on a path are dropped.
+fn join_paths(
+    before: &HashMap<String, VarInfo>,
+    paths: &[HashMap<String, VarInfo>],
+) -> HashMap<String, VarInfo> {
+    if paths.is_empty() {
+        return before.clone();
+    }
+    let mut out = HashMap::new();
+    for name in before.keys() {
+        let infos: Vec<&VarInfo> = paths.iter().filter_map(|e| e.get(name)).collect();
+        let Some(first) = infos.first() else {
+            continue;
+        };
+        let mut v = (*first).clone();
+        v.label = infos.iter().map(|i| i.label).max().unwrap_or_default();
+        v.moved = infos.iter().find_map(|i| i.moved.as_ref()).map(|m| Move {
+            span: m.span.clone(),
+            maybe: !infos.iter().all(|i| i.surely_moved(&[])),
+        });
+        v.moved_fields = Vec::new();
+        for (p, m) in infos.iter().flat_map(|i| &i.moved_fields) {
+            if v.moved_fields.iter().any(|(q, _)| q == p) {
+                continue;
+            }
+            let maybe = !infos.iter().all(|i| i.surely_moved(p));
+            v.moved_fields.push((
+                p.clone(),
+                Move {
+                    span: m.span.clone(),
+                    maybe,
+                },
+            ));
+        }
+        out.insert(name.clone(), v);
+    }
+    out
 }
 
-fn lower_type(t: &Type) -> Ty {
-    match t {
-        Type::Int { .. } => Ty::Int,
-        Type::Bool { .. } => Ty::Bool,
-        Type::Unit { .. } => Ty::Unit,
-        Type::Secret { inner, .. } => lower_type(inner),
-        Type::Named { .. } => Ty::Unknown,
+/// Control never reaches the end of the block: it returns on every path.
+fn block_diverges(b: &Block) -> bool {
+    b.stmts.iter().any(stmt_diverges)
+}
+
+/// Control never continues after the statement: a `return`, or an `if`/`else` or `match`
+/// whose branches all return. Loops may run zero times, so they never diverge.
+fn stmt_diverges(s: &Stmt) -> bool {
+    match s {
+        Stmt::Return { .. } => true,
+        Stmt::If {
+            then_blk,
+            else_blk: Some(eb),
+            ..
+        } => block_diverges(then_blk) && block_diverges(eb),
+        Stmt::Match { arms, .. } => {
+            !arms.is_empty() && arms.iter().all(|a| block_diverges(&a.body))
+        }
+        _ => false,
     }
 }
 
-fn effect_name(e: Effect) -> &'static str {
-    match e {
-        Effect::Io => "io",
-        Effect::Net => "net",
-        Effect::Time => "time",
-        Effect::Rand => "rand",
+/// Field types of a declared struct, in declaration order.
+#[derive(Debug, Clone, Default)]
+struct StructInfo {
+    fields: Vec<FieldInfo>,
+    /// Contains a secret field (directly or in a nested struct): values are move-only.
+    linear: bool,
+}
+
+#[derive(Debug, Clone)]
+struct FieldInfo {
+    name: String,
+    ty: Ty,
+    label: Label,
+    written: String, // the type as written, for diagnostics
+}
+
+/// Variants of a declared enum, in declaration order.
+#[derive(Debug, Clone, Default)]
+struct EnumInfo {
+    variants: Vec<VariantInfo>,
+    /// Some payload is secret (or move-only): values are move-only.
+    linear: bool,
+}
+
+#[derive(Debug, Clone)]
+struct VariantInfo {
+    name: String,
+    payload: Vec<(Ty, Label)>,
+}
+
+/// The user-defined types of the program.
+#[derive(Debug, Default)]
+struct TypeDefs {
+    structs: HashMap<String, StructInfo>,
+    enums: HashMap<String, EnumInfo>,
+    /// Alias name -> the written type it stands for (`Type::Error` if the alias is cyclic).
+    aliases: HashMap<String, Type>,
+    /// Top-level constants; array lengths and loop bounds may use them.
+    consts: HashMap<String, ConstInfo>,
+    /// The declared label order, lowest first (`public < secret` if none is declared).
+    labels: Vec<String>,
+}
+
+/// A compile-time value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ConstVal {
+    Int(i64),
+    Bool(bool),
+    Str(String),
+}
+
+impl ConstVal {
+    fn ty(&self) -> Ty {
+        match self {
+            ConstVal::Int(_) => Ty::Int,
+            ConstVal::Bool(_) => Ty::Bool,
+            ConstVal::Str(_) => Ty::Str,
+        }
     }
 }
 
-fn fmt_effects_list(effs: &BTreeSet<Effect>) -> String {
-    let mut v: Vec<&'static str> = effs.iter().map(|e| effect_name(*e)).collect();
-    v.sort();
-    v.join(", ")
+#[derive(Debug, Clone)]
+struct ConstInfo {
+    ty: Ty,
+    label: Label,
+    value: Option<ConstVal>, // None if the initializer was rejected (already reported)
 }
 
-fn effects_set_of_fn(f: &FnDecl) -> BTreeSet<Effect> {
-    let mut set = BTreeSet::new();
-    for s in &f.effects {
-        set.insert(s.effect);
+impl TypeDefs {
+    fn contains(&self, name: &str) -> bool {
+        self.structs.contains_key(name)
+            || self.enums.contains_key(name)
+            || self.aliases.contains_key(name)
+    }
+
+    fn label(&self, name: &str) -> Option<Label> {
+        self.labels.iter().position(|l| l == name).map(Label)
+    }
+
+    /// The highest label, carried by `secret T`.
+    fn top(&self) -> Label {
+        Label(self.labels.len().saturating_sub(1))
+    }
+
+    fn label_name(&self, l: Label) -> &str {
+        &self.labels[l.0]
+    }
+
+    /// `Outcome::Ok` -> the enum `Outcome` and its variant `Ok`.
+    fn variant(&self, path: &str) -> Option<(&str, &VariantInfo)> {
+        let (enum_name, variant) = path.rsplit_once("::")?;
+        let (enum_name, info) = self.enums.get_key_value(enum_name)?;
+        let v = info.variants.iter().find(|v| v.name == variant)?;
+        Some((enum_name, v))
     }
-    set
 }
 
-fn pretty_ret(t: &Type) -> &'static str {
+fn ty_name(t: &Ty) -> String {
     match t {
-        Type::Int { .. } => "Int",
-        Type::Bool { .. } => "Bool",
-        Type::Unit { .. } => "Unit",
-        Type::Secret { .. } => "secret ...",
-        Type::Named { .. } => "...",
+        Ty::Int => "Int".into(),
+        Ty::Bool => "Bool".into(),
+        Ty::Unit => "Unit".into(),
+        Ty::Str => "Str".into(),
+        Ty::Struct(n) | Ty::Enum(n) | Ty::Param { name: n, .. } => n.clone(),
+        Ty::Bytes(n) => format!("Bytes[{}]", n),
+        Ty::Var(_) => "_".into(),
+        Ty::Error => "{error}".into(),
     }
 }
 
-fn make_effects_fix(f: &FnDecl, proposed: &BTreeSet<Effect>) -> (String, String) {
-    let replace = format!("fn {}(...) -> {} {{", f.name.name, pretty_ret(&f.ret));
-    let with = format!(
-        "fn {}(...) -> {} effects({}) {{",
-        f.name.name,
-        pretty_ret(&f.ret),
-        fmt_effects_list(proposed)
-    );
-    (replace, with)
+/// The label of a written type: the highest for `secret T`, the named one for
+/// `labeled<l> T` (directly or through type aliases), else public. Unknown labels are
+/// reported by [`lower_checked`] and count as public.
+fn label_of_type(t: &Type, defs: &TypeDefs) -> Label {
+    match t {
+        Type::Secret { .. } => defs.top(),
+        Type::Labeled { label, inner, .. } => defs
+            .label(&label.name)
+            .unwrap_or_default()
+            .join(label_of_type(inner, defs)),
+        Type::Named { name, .. } => defs
+            .aliases
+            .get(name)
+            .map_or(Label::PUBLIC, |a| label_of_type(a, defs)),
+        _ => Label::PUBLIC,
+    }
 }
 
-pub fn typecheck(p: &Program) -> Result<(), DiagnosticReport> {
-    let mut r = DiagnosticReport::new();
+/// The type under any `secret` / `labeled<l>` wrappers.
+fn unlabeled(mut t: &Type) -> &Type {
+    while let Type::Secret { inner, .. } | Type::Labeled { inner, .. } = t {
+        t = inner;
+    }
+    t
+}
 
-    let mut fn_effects: HashMap<String, BTreeSet<Effect>> = HashMap::new();
-    for f in &p.funcs {
-        fn_effects.insert(f.name.name.clone(), effects_set_of_fn(f));
+/// A written type as diagnostics print it: alias and type parameter names as the user
+/// wrote them, anything else as lowered (labels are reported separately).
+fn written_type(t: &Type, ty: &Ty) -> String {
+    match unlabeled(t) {
+        Type::Named { name, .. } => name.clone(),
+        _ => ty_name(ty),
     }
+}
 
-    check_main(p, &mut r);
+/// `tparams` are the type parameters in scope (`Ty::Param`s of the enclosing function).
+fn lower_type(t: &Type, defs: &TypeDefs, tparams: &[Ty]) -> Ty {
+    match t {
+        Type::Int { .. } => Ty::Int,
+        Type::Bool { .. } => Ty::Bool,
+        Type::Unit { .. } => Ty::Unit,
+        Type::Str { .. } => Ty::Str,
+        Type::Secret { inner, .. } | Type::Labeled { inner, .. } => {
+            lower_type(inner, defs, tparams)
+        }
+        Type::Named { name, .. } => {
+            if let Some(p) = tparams
+                .iter()
+                .find(|p| matches!(p, Ty::Param { name: n, .. } if n == name))
+            {
+                p.clone()
+            } else if defs.structs.contains_key(name) {
+                Ty::Struct(name.clone())
+            } else if defs.enums.contains_key(name) {
+                Ty::Enum(name.clone())
+            } else if let Some(a) = defs.aliases.get(name) {
+                lower_type(a, defs, &[])
+            } else {
+                Ty::Error
+            }
+        }
+        Type::Bytes { len, .. } => match const_int(len, defs) {
+            Some(n) if n >= 0 => Ty::Bytes(n as u64),
+            _ => Ty::Error,
+        },
+        Type::Error { .. } => Ty::Error,
+    }
+}
 
-    typecheck_protocols(&p.protocols, &mut r);
+/// The type parameters of `f`, as seen inside its body.
+fn generics_of(f: &FnDecl) -> Vec<Ty> {
+    f.generics
+        .iter()
+        .map(|g| Ty::Param {
+            name: g.name.name.clone(),
+            copy: g.bounds.iter().any(|b| b.name == "Copy"),
+        })
+        .collect()
+}
 
-    for f in &p.funcs {
-        typecheck_fn(f, &fn_effects, &mut r);
+/// `T` -> the inference variable standing for `T` at one call site.
+fn instantiate(t: &Ty, inst: &[(String, Ty)]) -> Ty {
+    match t {
+        Ty::Param { name, .. } => inst
+            .iter()
+            .find(|(n, _)| n == name)
+            .map_or(Ty::Error, |(_, v)| v.clone()),
+        _ => t.clone(),
     }
+}
 
-    if r.is_ok() {
-        Ok(())
+/// `length-mismatch` when both types are byte arrays (of different lengths), else `default`.
+fn mismatch_code<'c>(want: &Ty, got: &Ty, default: &'c str) -> &'c str {
+    if matches!((want, got), (Ty::Bytes(_), Ty::Bytes(_))) {
+        "length-mismatch"
     } else {
-        Err(r)
+        default
     }
 }
 
-fn check_main(p: &Program, r: &mut DiagnosticReport) {
-    let mains: Vec<&FnDecl> = p.funcs.iter().filter(|f| f.name.name == "main").collect();
+/// Values of this type must be moved, not copied: secrets, and structs and enums that
+/// hold secrets.
+fn is_linear(ty: &Ty, is_secret: bool, defs: &TypeDefs) -> bool {
+    is_secret
+        || match ty {
+            Ty::Struct(n) => defs.structs.get(n).is_some_and(|s| s.linear),
+            Ty::Enum(n) => defs.enums.get(n).is_some_and(|e| e.linear),
+            Ty::Param { copy, .. } => !copy,
+            _ => false,
+        }
+}
 
-    if mains.is_empty() {
-        r.push(Diagnostic::error(
-            "main-missing",
-            "Missing `fn main() -> Unit { ... }`.",
-            p.span.clone(),
-        ));
-        return;
+/// Whether the place `path` of a value of type `ty` still holds secret data that has not
+/// been moved out (`moved` are the partial moves of the variable).
+fn holds_live_secret(
+    ty: &Ty,
+    is_secret: bool,
+    path: &mut Vec<String>,
+    moved: &[(Vec<String>, Move)],
+    defs: &TypeDefs,
+) -> bool {
+    if moved.iter().any(|(p, m)| !m.maybe && path.starts_with(p)) {
+        return false;
     }
-
-    if mains.len() > 1 {
-        r.push(Diagnostic::warning(
-            "main-duplicate",
-            "Multiple `main` functions found.",
-            mains[1].span.clone(),
-        ));
+    match ty {
+        Ty::Struct(n) if defs.structs.contains_key(n) => defs.structs[n].fields.iter().any(|f| {
+            path.push(f.name.clone());
+            let live =
+                holds_live_secret(&f.ty, is_secret || f.label.is_secret(), path, moved, defs);
+            path.pop();
+            live
+        }),
+        _ => is_linear(ty, is_secret, defs),
     }
+}
 
-    let m = mains[0];
-
-    if !m.params.is_empty() {
+/// The label order: the one `label` declaration, or `public < secret`. A second
+/// declaration or a repeated name is `label-duplicate`.
+fn collect_labels(p: &Program, r: &mut DiagnosticReport) -> Vec<String> {
+    let Some(first) = p.labels.first() else {
+        return vec!["public".into(), "secret".into()];
+    };
+    for d in &p.labels[1..] {
         r.push(Diagnostic::error(
-            "main-invalid-signature",
-            "main must have zero parameters.",
-            m.span.clone(),
+            "label-duplicate",
+            format!(
+                "Labels are already declared at line {}; a program has one label order.",
+                first.span.start_line
+            ),
+            d.span.clone(),
         ));
     }
-
-    let ret = lower_type(&m.ret);
-    if ret != Ty::Unit {
-        r.push(Diagnostic::error(
-            "main-invalid-signature",
-            "main must return Unit.",
-            m.ret.span().clone(),
-        ));
+    let mut labels: Vec<String> = Vec::new();
+    for n in &first.names {
+        if labels.contains(&n.name) {
+            r.push(Diagnostic::error(
+                "label-duplicate",
+                format!("Label `{}` appears twice in the label order.", n.name),
+                n.span.clone(),
+            ));
+            continue;
+        }
+        labels.push(n.name.clone());
     }
+    labels
 }
 
-fn typecheck_fn(
-    f: &FnDecl,
-    fn_effects: &HashMap<String, BTreeSet<Effect>>,
-    r: &mut DiagnosticReport,
-) {
-    let ret = lower_type(&f.ret);
-    let current_effects = effects_set_of_fn(f);
-
-    let mut env: HashMap<String, VarInfo> = HashMap::new();
+fn collect_type_defs(p: &Program, r: &mut DiagnosticReport) -> TypeDefs {
+    let mut defs = TypeDefs {
+        labels: collect_labels(p, r),
+        ..TypeDefs::default()
+    };
 
-    for p in &f.params {
-        let pt = lower_type(&p.ty);
-        if pt == Ty::Unknown {
+    let mut structs = Vec::new();
+    for d in &p.structs {
+        if defs.contains(&d.name.name) {
             r.push(Diagnostic::error(
-                "type-unknown",
-                "Unknown parameter type (Candy supports Int|Bool|Unit and secret wrappers).",
-                p.ty.span().clone(),
+                "struct-duplicate",
+                format!("Duplicate type `{}`.", d.name.name),
+                d.name.span.clone(),
             ));
+            continue;
         }
-        env.insert(
-            p.name.name.clone(),
-            VarInfo {
-                ty: pt,
-                is_secret: is_secret_type(&p.ty),
-                moved: false,
+        defs.structs
+            .insert(d.name.name.clone(), StructInfo::default());
+        structs.push(d);
+    }
+    let mut enums = Vec::new();
+    for d in &p.enums {
+        if defs.contains(&d.name.name) {
+            r.push(Diagnostic::error(
+                "enum-duplicate",
+                format!("Duplicate type `{}`.", d.name.name),
+                d.name.span.clone(),
+            ));
+            continue;
+        }
+        defs.enums.insert(d.name.name.clone(), EnumInfo::default());
+        enums.push(d);
+    }
+    let mut aliases = Vec::new();
+    for d in &p.aliases {
+        if defs.contains(&d.name.name) {
+            r.push(Diagnostic::error(
+                "alias-duplicate",
+                format!("Duplicate type `{}`.", d.name.name),
+                d.name.span.clone(),
+            ));
+            continue;
+        }
+        defs.aliases.insert(d.name.name.clone(), d.ty.clone());
+        aliases.push(d);
+    }
+
+    // An alias that expands to itself never reaches a real type.
+    let cyclic: Vec<&Ident> = aliases
+        .iter()
+        .filter_map(|d| {
+            let cycle = alias_cycle(&d.name.name, &defs)?;
+            r.push(Diagnostic::error(
+                "alias-cycle",
+                format!(
+                    "Type alias `{}` expands to itself ({}).",
+                    d.name.name,
+                    cycle.join(" -> ")
+                ),
+                d.name.span.clone(),
+            ));
+            Some(&d.name)
+        })
+        .collect();
+    for name in cyclic {
+        defs.aliases.insert(
+            name.name.clone(),
+            Type::Error {
+                span: name.span.clone(),
             },
         );
     }
+    for d in &aliases {
+        let _ = lower_checked(&d.ty, &defs, &[], "aliased", r);
+    }
+
+    defs.consts = collect_consts(p, &defs, r);
+
+    for d in &structs {
+        let mut fields: Vec<FieldInfo> = Vec::new();
+        for fd in &d.fields {
+            if fields.iter().any(|f| f.name == fd.name.name) {
+                r.push(Diagnostic::error(
+                    "struct-duplicate-field",
+                    format!(
+                        "Duplicate field `{}` in struct `{}`.",
+                        fd.name.name, d.name.name
+                    ),
+                    fd.span.clone(),
+                ));
+                continue;
+            }
+            let ty = lower_checked(&fd.ty, &defs, &[], "field", r);
+            fields.push(FieldInfo {
+                name: fd.name.name.clone(),
+                written: written_type(&fd.ty, &ty),
+                ty,
+                label: label_of_type(&fd.ty, &defs),
+            });
+        }
+        defs.structs
+            .get_mut(&d.name.name)
+            .expect("inserted above")
+            .fields = fields;
+    }
+
+    for d in &enums {
+        let mut variants: Vec<VariantInfo> = Vec::new();
+        for vd in &d.variants {
+            if variants.iter().any(|v| v.name == vd.name.name) {
+                r.push(Diagnostic::error(
+                    "enum-duplicate-variant",
+                    format!(
+                        "Duplicate variant `{}` in enum `{}`.",
+                        vd.name.name, d.name.name
+                    ),
+                    vd.span.clone(),
+                ));
+                continue;
+            }
+            let payload = vd
+                .payload
+                .iter()
+                .map(|t| {
+                    (
+                        lower_checked(t, &defs, &[], "payload", r),
+                        label_of_type(t, &defs),
+                    )
+                })
+                .collect();
+            variants.push(VariantInfo {
+                name: vd.name.name.clone(),
+                payload,
+            });
+        }
+        defs.enums
+            .get_mut(&d.name.name)
+            .expect("inserted above")
+            .variants = variants;
+    }
+
+    // A type that contains itself by value has no finite layout.
+    for (code, kind, name) in structs
+        .iter()
+        .map(|d| ("struct-recursive", "Struct", &d.name))
+        .chain(enums.iter().map(|d| ("enum-recursive", "Enum", &d.name)))
+    {
+        if type_reaches(&name.name, &name.name, &defs, &mut Vec::new()) {
+            r.push(Diagnostic::error(
+                code,
+                format!(
+                    "{} `{}` contains itself; recursive types have infinite size.",
+                    kind, name.name
+                ),
+                name.span.clone(),
+            ));
+        }
+    }
+
+    // Linearity propagates through nested types until nothing changes.
+    loop {
+        let mut changed = false;
+        let names: Vec<String> = defs.structs.keys().cloned().collect();
+        for n in names {
+            if !defs.structs[&n].linear
+                && defs.structs[&n]
+                    .fields
+                    .iter()
+                    .any(|f| is_linear(&f.ty, f.label.is_secret(), &defs))
+            {
+                defs.structs.get_mut(&n).expect("known struct").linear = true;
+                changed = true;
+            }
+        }
+        let names: Vec<String> = defs.enums.keys().cloned().collect();
+        for n in names {
+            if !defs.enums[&n].linear
+                && defs.enums[&n]
+                    .variants
+                    .iter()
+                    .flat_map(|v| &v.payload)
+                    .any(|(t, l)| is_linear(t, l.is_secret(), &defs))
+            {
+                defs.enums.get_mut(&n).expect("known enum").linear = true;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    defs
+}
+
+/// `[A, B, A]` if expanding the alias `start` leads back to it.
+fn alias_cycle(start: &str, defs: &TypeDefs) -> Option<Vec<String>> {
+    let mut path = vec![start.to_string()];
+    let mut cur = defs.aliases.get(start)?;
+    loop {
+        let Type::Named { name, .. } = unlabeled(cur) else {
+            return None;
+        };
+        let next = defs.aliases.get(name)?;
+        if name == start {
+            path.push(name.clone());
+            return Some(path);
+        }
+        if path.contains(name) {
+            return None; // a cycle that `start` only leads into
+        }
+        path.push(name.clone());
+        cur = next;
+    }
+}
+
+/// Lowers a written type; `type-unknown` if it names no type, `length-not-constant` for
+/// `Bytes[N]` without a constant length, `label-unknown` for an undeclared label. `what`
+/// names the position ("parameter", ...).
+fn lower_checked(
+    t: &Type,
+    defs: &TypeDefs,
+    tparams: &[Ty],
+    what: &str,
+    r: &mut DiagnosticReport,
+) -> Ty {
+    let mut cur = t;
+    while let Type::Secret { inner, .. } | Type::Labeled { inner, .. } = cur {
+        if let Type::Labeled { label, .. } = cur {
+            if defs.label(&label.name).is_none() {
+                r.push(Diagnostic::error(
+                    "label-unknown",
+                    format!(
+                        "Unknown label `{}`; declared labels: {}.",
+                        label.name,
+                        defs.labels.join(" < ")
+                    ),
+                    label.span.clone(),
+                ));
+            }
+        }
+        cur = inner;
+    }
+    let ty = lower_type(t, defs, tparams);
+
+    if ty != Ty::Error {
+        return ty;
+    }
+    match unlabeled(t) {
+        // already reported by the parser
+        Type::Error { .. } => {}
+        // a broken alias is reported at its declaration
+        Type::Named { name, .. } if defs.aliases.contains_key(name) => {}
+        Type::Bytes { len, .. } => r.push(Diagnostic::error(
+            "length-not-constant",
+            "Array length must be a non-negative compile-time constant Int.",
+            len.span().clone(),
+        )),
+        _ => r.push(Diagnostic::error(
+            "type-unknown",
+            format!(
+                "Unknown {} type (Candy supports Int|Bool|Str|Unit|Bytes[N], structs, enums and secret wrappers).",
+                what
+            ),
+            t.span().clone(),
+        )),
+    }
+    ty
+}
+
+/// Whether the type named `from` contains a value of the type `target`, transitively.
+fn type_reaches(from: &str, target: &str, defs: &TypeDefs, seen: &mut Vec<String>) -> bool {
+    let parts: Vec<&Ty> = if let Some(s) = defs.structs.get(from) {
+        s.fields.iter().map(|f| &f.ty).collect()
+    } else if let Some(e) = defs.enums.get(from) {
+        e.variants
+            .iter()
+            .flat_map(|v| v.payload.iter().map(|(t, _)| t))
+            .collect()
+    } else {
+        return false;
+    };
+    for t in parts {
+        if let Ty::Struct(n) | Ty::Enum(n) = t {
+            if n == target {
+                return true;
+            }
+            if !seen.contains(n) {
+                seen.push(n.clone());
+                if type_reaches(n, target, defs, seen) {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Type and label of `ty.field`; `field-unknown` if there is no such field.
+fn field_of(
+    ty: &Ty,
+    label: Label,
+    field: &Ident,
+    defs: &TypeDefs,
+    r: &mut DiagnosticReport,
+) -> (Ty, Label) {
+    if *ty == Ty::Error {
+        return (Ty::Error, label);
+    }
+    if let Ty::Var(_) = ty {
+        r.push(Diagnostic::error(
+            "type-ambiguous",
+            format!(
+                "Cannot infer the type whose field `{}` is read; add a type annotation.",
+                field.name
+            ),
+            field.span.clone(),
+        ));
+        return (Ty::Error, label);
+    }
+    let found = match ty {
+        Ty::Struct(n) => defs
+            .structs
+            .get(n)
+            .and_then(|s| s.fields.iter().find(|f| f.name == field.name)),
+        _ => None,
+    };
+    match found {
+        Some(f) => (f.ty.clone(), label.join(f.label)),
+        None => {
+            r.push(Diagnostic::error(
+                "field-unknown",
+                format!("Type {} has no field `{}`.", ty_name(ty), field.name),
+                field.span.clone(),
+            ));
+            (Ty::Error, label)
+        }
+    }
+}
+
+/// `label-flow` if data labeled `from` reaches a non-public position labeled `to` below it
+/// (a parameter, a return type, or an annotated `let` and assignments to it).
+/// (Flows into public positions are `secret-leak-param` / `secret-leak-return`.)
+fn check_label_flow(
+    from: Label,
+    to: Label,
+    what: &str,
+    span: &Span,
+    defs: &TypeDefs,
+    r: &mut DiagnosticReport,
+) {
+    if from <= to || !to.is_secret() {
+        return;
+    }
+    r.push(Diagnostic::error(
+        "label-flow",
+        format!(
+            "Data labeled `{}` flows into {}, which is labeled `{}`; only `declassify` may lower a label.",
+            defs.label_name(from),
+            what,
+            defs.label_name(to)
+        ),
+        span.clone(),
+    ));
+}
+
+/// `x.a.b` as the variable `x` and the field path `[a, b]`.
+fn place_of(e: &Expr) -> Option<(&Ident, Vec<&Ident>)> {
+    match e {
+        Expr::Var { name, .. } => Some((name, Vec::new())),
+        Expr::Field { base, field, .. } => {
+            let (v, mut path) = place_of(base)?;
+            path.push(field);
+            Some((v, path))
+        }
+        _ => None,
+    }
+}
+
+fn place_name(var: &str, path: &[String]) -> String {
+    std::iter::once(var)
+        .chain(path.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// `use-after-move` (or `use-after-maybe-move`) for the place `var.path` if it (or part of it) was moved.
+/// The variable is then exempt from `secret-not-consumed`.
+fn check_place_live(
+    v: &VarInfo,
+    var: &Ident,
+    path: &[String],
+    span: &Span,
+    cx: &FnCtx,
+    r: &mut DiagnosticReport,
+) -> bool {
+    let Some((moved_path, m)) = v.move_conflict(path) else {
+        return true;
+    };
+    cx.unconsumed.borrow_mut().insert(decl_key(v));
+
+    let place = place_name(&var.name, path);
+    let (code, paths) = if m.maybe {
+        ("use-after-maybe-move", " on some paths")
+    } else {
+        ("use-after-move", "")
+    };
+    let msg = if moved_path == path {
+        format!("Use of `{}` after it was moved{}.", place, paths)
+    } else {
+        format!(
+            "Use of `{}` after `{}` was moved{}.",
+            place,
+            place_name(&var.name, moved_path),
+            paths
+        )
+    };
+    r.push(Diagnostic::error(code, msg, span.clone()));
+    false
+}
+
+fn effect_name(e: Effect) -> &'static str {
+    match e {
+        Effect::Io => "io",
+        Effect::Net => "net",
+        Effect::Time => "time",
+        Effect::Rand => "rand",
+        Effect::Declassify => "declassify",
+    }
+}
+
+fn fmt_effects_list(effs: &BTreeSet<Effect>) -> String {
+    let mut v: Vec<&'static str> = effs.iter().map(|e| effect_name(*e)).collect();
+    v.sort();
+    v.join(", ")
+}
+
+fn effects_set_of_fn(f: &FnDecl) -> BTreeSet<Effect> {
+    let mut set = BTreeSet::new();
+    for s in &f.effects {
+        set.insert(s.effect);
+    }
+    set
+}
+
+fn pretty_ret(t: &Type) -> &'static str {
+    match t {
+        Type::Int { .. } => "Int",
+        Type::Bool { .. } => "Bool",
+        Type::Unit { .. } => "Unit",
+        Type::Str { .. } => "Str",
+        Type::Secret { .. } => "secret ...",
+        Type::Labeled { .. } => "labeled<...> ...",
+        Type::Named { .. } | Type::Bytes { .. } | Type::Error { .. } => "...",
+    }
+}
+
+fn make_effects_fix(f: &FnDecl, proposed: &BTreeSet<Effect>) -> (String, String) {
+    // Imported functions carry their qualified name; the source says only the last segment.
+    let name = f.name.name.rsplit("::").next().unwrap_or(&f.name.name);
+    let ct = if f.ct { " ct" } else { "" };
+    let replace = format!("fn {}(...) -> {}{} {{", name, pretty_ret(&f.ret), ct);
+    let with = format!(
+        "fn {}(...) -> {}{} effects({}) {{",
+        name,
+        pretty_ret(&f.ret),
+        ct,
+        fmt_effects_list(proposed)
+    );
+    (replace, with)
+}
+
+pub fn typecheck(p: &Program) -> Result<(), DiagnosticReport> {
+    let r = typecheck_with_warnings(p);
+    if r.is_ok() {
+        Ok(())
+    } else {
+        Err(r)
+    }
+}
+
+/// Like [`typecheck`], but returns the whole report, so warnings such as
+/// `unreachable-code` are kept when there are no errors.
+pub fn typecheck_with_warnings(p: &Program) -> DiagnosticReport {
+    let mut r = DiagnosticReport::new();
+
+    let defs = collect_type_defs(p, &mut r);
+    let fns = collect_fn_sigs(p, &defs, &mut r);
+
+    check_main(p, &defs, &mut r);
+
+    typecheck_protocols(&p.protocols, &mut r);
+
+    for f in &p.funcs {
+        typecheck_fn(f, &fns, &defs, &mut r);
+    }
+
+    r
+}
+
+/// The checked signature of a user function, as seen by its callers.
+#[derive(Debug, Clone)]
+struct FnSig {
+    generics: Vec<Ty>,
+    params: Vec<(Ty, Label, String)>, // (type, label, as written)
+    ret: Ty,
+    ret_label: Label,
+    ct: bool,
+    effects: BTreeSet<Effect>,
+}
+
+/// Signatures of all user functions. Types that fail to lower are reported when the
+/// function itself is checked; a second definition of a name is `fn-duplicate` (a second
+/// `main` is reported by [`check_main`]) and callers see the first.
+fn collect_fn_sigs(
+    p: &Program,
+    defs: &TypeDefs,
+    r: &mut DiagnosticReport,
+) -> HashMap<String, FnSig> {
+    let mut fns = HashMap::new();
+    for f in &p.funcs {
+        if fns.contains_key(&f.name.name) {
+            if f.name.name != "main" {
+                r.push(Diagnostic::error(
+                    "fn-duplicate",
+                    format!("Duplicate function `{}`.", f.name.name),
+                    f.name.span.clone(),
+                ));
+            }
+            continue;
+        }
+        let generics = generics_of(f);
+        fns.insert(
+            f.name.name.clone(),
+            FnSig {
+                params: f
+                    .params
+                    .iter()
+                    .map(|p| {
+                        let ty = lower_type(&p.ty, defs, &generics);
+                        let written = written_type(&p.ty, &ty);
+                        (ty, label_of_type(&p.ty, defs), written)
+                    })
+                    .collect(),
+                ret: lower_type(&f.ret, defs, &generics),
+                ret_label: label_of_type(&f.ret, defs),
+                ct: f.ct,
+                effects: effects_set_of_fn(f),
+                generics,
+            },
+        );
+    }
+    fns
+}
+
+/// A `declassify(x, "reason")` site, for the audit report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declassification {
+    pub function: String,
+    pub reason: String,
+    pub span: Span,
+}
+
+/// Every `declassify` call in the program, in source order. Calls without a string
+/// literal reason are listed with an empty reason (the checker rejects them).
+pub fn declassifications(p: &Program) -> Vec<Declassification> {
+    let mut out = Vec::new();
+    for f in &p.funcs {
+        for s in &f.body.stmts {
+            declassifications_in_stmt(s, &f.name.name, &mut out);
+        }
+    }
+    out
+}
+
+fn declassifications_in_stmt(s: &Stmt, func: &str, out: &mut Vec<Declassification>) {
+    let block = |b: &Block, out: &mut Vec<Declassification>| {
+        for s in &b.stmts {
+            declassifications_in_stmt(s, func, out);
+        }
+    };
+    match s {
+        Stmt::Let { expr, .. } | Stmt::Assign { expr, .. } | Stmt::Expr { expr, .. } => {
+            declassifications_in_expr(expr, func, out)
+        }
+        Stmt::Return { expr, .. } => {
+            if let Some(e) = expr {
+                declassifications_in_expr(e, func, out);
+            }
+        }
+        Stmt::If {
+            cond,
+            then_blk,
+            else_blk,
+            ..
+        } => {
+            declassifications_in_expr(cond, func, out);
+            block(then_blk, out);
+            if let Some(b) = else_blk {
+                block(b, out);
+            }
+        }
+        Stmt::For {
+            start, end, body, ..
+        } => {
+            declassifications_in_expr(start, func, out);
+            declassifications_in_expr(end, func, out);
+            block(body, out);
+        }
+        Stmt::Match {
+            scrutinee, arms, ..
+        } => {
+            declassifications_in_expr(scrutinee, func, out);
+            for arm in arms {
+                block(&arm.body, out);
+            }
+        }
+        Stmt::Error { .. } => {}
+    }
+}
+
+fn declassifications_in_expr(e: &Expr, func: &str, out: &mut Vec<Declassification>) {
+    match e {
+        Expr::Call { callee, args, span } => {
+            if callee.name == "declassify" {
+                let reason = match args.get(1) {
+                    Some(Expr::StrLit { value, .. }) => value.clone(),
+                    _ => String::new(),
+                };
+                out.push(Declassification {
+                    function: func.to_string(),
+                    reason,
+                    span: span.clone(),
+                });
+            }
+            for a in args {
+                declassifications_in_expr(a, func, out);
+            }
+        }
+        Expr::Field { base, .. } => declassifications_in_expr(base, func, out),
+        Expr::Index { base, index, .. } => {
+            declassifications_in_expr(base, func, out);
+            declassifications_in_expr(index, func, out);
+        }
+        Expr::Slice {
+            base, start, end, ..
+        } => {
+            declassifications_in_expr(base, func, out);
+            declassifications_in_expr(start, func, out);
+            declassifications_in_expr(end, func, out);
+        }
+        Expr::ArrayLit { elems, .. } => {
+            for x in elems {
+                declassifications_in_expr(x, func, out);
+            }
+        }
+        Expr::ArrayRepeat { elem, count, .. } => {
+            declassifications_in_expr(elem, func, out);
+            declassifications_in_expr(count, func, out);
+        }
+        Expr::StructLit { fields, .. } => {
+            for fi in fields {
+                declassifications_in_expr(&fi.expr, func, out);
+            }
+        }
+        Expr::Unary { expr, .. } => declassifications_in_expr(expr, func, out),
+        Expr::Binary { lhs, rhs, .. } => {
+            declassifications_in_expr(lhs, func, out);
+            declassifications_in_expr(rhs, func, out);
+        }
+        Expr::IntLit { .. }
+        | Expr::BoolLit { .. }
+        | Expr::StrLit { .. }
+        | Expr::Var { .. }
+        | Expr::Move { .. }
+        | Expr::Error { .. } => {}
+    }
+}
+
+fn check_main(p: &Program, defs: &TypeDefs, r: &mut DiagnosticReport) {
+    let mains: Vec<&FnDecl> = p.funcs.iter().filter(|f| f.name.name == "main").collect();
+
+    if mains.is_empty() {
+        r.push(Diagnostic::error(
+            "main-missing",
+            "Missing `fn main() -> Unit { ... }`.",
+            p.span.clone(),
+        ));
+        return;
+    }
+
+    if mains.len() > 1 {
+        r.push(Diagnostic::warning(
+            "main-duplicate",
+            "Multiple `main` functions found.",
+            mains[1].span.clone(),
+        ));
+    }
+
+    let m = mains[0];
+
+    if !m.params.is_empty() {
+        r.push(Diagnostic::error(
+            "main-invalid-signature",
+            "main must have zero parameters.",
+            m.span.clone(),
+        ));
+    }
+
+    if !m.generics.is_empty() {
+        r.push(Diagnostic::error(
+            "main-invalid-signature",
+            "main cannot have type parameters.",
+            m.span.clone(),
+        ));
+    }
+
+    let ret = lower_type(&m.ret, defs, &[]);
+    if ret != Ty::Unit {
+        r.push(Diagnostic::error(
+            "main-invalid-signature",
+            "main must return Unit.",
+            m.ret.span().clone(),
+        ));
+    }
+}
+
+/// Per-function state shared by the statement and expression checkers.
+#[derive(Clone, Copy)]
+struct FnCtx<'a> {
+    current_fn: &'a FnDecl,
+    ret: &'a Ty,
+    current_effects: &'a BTreeSet<Effect>,
+    generics: &'a [Ty],
+    fns: &'a HashMap<String, FnSig>,
+    defs: &'a TypeDefs,
+    infer: &'a RefCell<Infer>,
+    /// Names whose block has ended, for `name-out-of-scope`.
+    ended: &'a RefCell<HashSet<String>>,
+    /// Declarations exempt from `secret-not-consumed` (file, line, column): already reported,
+    /// or copied (`secret-copy`).
+    unconsumed: &'a RefCell<HashSet<(String, u32, u32)>>,
+}
+
+impl FnCtx<'_> {
+    fn resolve(&self, t: &Ty) -> Ty {
+        self.infer.borrow().resolve(t)
+    }
+
+    fn unify(&self, a: &Ty, b: &Ty) -> bool {
+        self.infer.borrow_mut().unify(a, b)
+    }
+
+    /// `name-out-of-scope` if `name` was declared in a block that has ended, else `name-unknown`.
+    fn unknown_name(&self, name: &Ident) -> Diagnostic {
+        if self.ended.borrow().contains(&name.name) {
+            Diagnostic::error(
+                "name-out-of-scope",
+                format!(
+                    "`{}` is out of scope here; it was declared in a block that has ended.",
+                    name.name
+                ),
+                name.span.clone(),
+            )
+        } else {
+            Diagnostic::error(
+                "name-unknown",
+                format!("Unknown name `{}`.", name.name),
+                name.span.clone(),
+            )
+        }
+    }
+}
+
+/// Checks `b` as a scope of its own: its `let`s go out of scope at the end, and the outer
+/// bindings they shadowed are visible again. Code after a diverging statement is still
+/// checked, with one `unreachable-code` warning per block.
+fn typecheck_block(
+    b: &Block,
+    env: &mut HashMap<String, VarInfo>,
+    cx: &FnCtx,
+    r: &mut DiagnosticReport,
+) {
+    let mut diverged = false;
+    let mut warned = false;
+    for st in &b.stmts {
+        if diverged && !warned && !matches!(st, Stmt::Error { .. }) {
+            r.push(Diagnostic::warning(
+                "unreachable-code",
+                "Unreachable statement: every path before it returns.",
+                st.span().clone(),
+            ));
+            warned = true;
+        }
+        typecheck_stmt(st, env, cx, r);
+        diverged = diverged || stmt_diverges(st);
+    }
+    for st in b.stmts.iter().rev() {
+        let Stmt::Let { name, .. } = st else {
+            continue;
+        };
+        let Some(v) = env.remove(&name.name) else {
+            continue;
+        };
+        // A diverging block was checked at its `return`s.
+        if !diverged {
+            check_consumed(&name.name, &v, "}", cx, r);
+        }
+        match v.shadowed {
+            Some(outer) => {
+                env.insert(name.name.clone(), *outer);
+            }
+            None => {
+                cx.ended.borrow_mut().insert(name.name.clone());
+            }
+        }
+    }
+}
+
+/// `ct-violation` if the current function is `ct`; `reason` says what is not constant-time.
+fn check_ct(cx: &FnCtx, reason: &str, span: &Span, r: &mut DiagnosticReport) {
+    if cx.current_fn.ct {
+        r.push(Diagnostic::error(
+            "ct-violation",
+            format!("`{}` is `ct`, but {}.", cx.current_fn.name.name, reason),
+            span.clone(),
+        ));
+    }
+}
+
+/// The `return`s anywhere inside `b`.
+fn returns_in<'b>(b: &'b Block, out: &mut Vec<&'b Span>) {
+    for s in &b.stmts {
+        match s {
+            Stmt::Return { span, .. } => out.push(span),
+            Stmt::If {
+                then_blk, else_blk, ..
+            } => {
+                returns_in(then_blk, out);
+                if let Some(eb) = else_blk {
+                    returns_in(eb, out);
+                }
+            }
+            Stmt::For { body, .. } => returns_in(body, out),
+            Stmt::Match { arms, .. } => {
+                for arm in arms {
+                    returns_in(&arm.body, out);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `secret-not-consumed` when the binding `name` goes out of scope still holding secret data:
+/// it must be moved, returned, passed on or wiped first. `exit` is the `}` or `return` where
+/// the fix inserts `wipe(name);`. Each binding is reported once.
+fn check_consumed(name: &str, v: &VarInfo, exit: &str, cx: &FnCtx, r: &mut DiagnosticReport) {
+    // A binding whose value already has a diagnostic is not reported again.
+    if v.surely_moved(&[])
+        || cx.resolve(&v.ty) == Ty::Error
+        || !holds_live_secret(
+            &cx.resolve(&v.ty),
+            v.label.is_secret(),
+            &mut Vec::new(),
+            &v.moved_fields,
+            cx.defs,
+        )
+    {
+        return;
+    }
+    if !cx.unconsumed.borrow_mut().insert(decl_key(v)) {
+        return;
+    }
+    let what = match generic_param(&cx.resolve(&v.ty), v.label) {
+        Some(t) => format!(
+            "`{}` goes out of scope holding a value of type parameter `{}`, which may be secret and must be consumed",
+            name, t
+        ),
+        None => format!("`{}` goes out of scope still holding secret data", name),
+    };
+    r.push(
+        Diagnostic::error(
+            "secret-not-consumed",
+            format!(
+                "{}; move, return or pass it on, or destroy it with `wipe({})`.",
+                what, name
+            ),
+            v.decl.clone(),
+        )
+        .with_fix(exit.to_string(), format!("wipe({});\n{}", name, exit)),
+    );
+}
+
+/// The name of `T` when a value is move-only only because its type is an unbounded type
+/// parameter `T`, which a caller may instantiate with a secret.
+fn generic_param(ty: &Ty, label: Label) -> Option<&str> {
+    match ty {
+        Ty::Param { name, copy: false } if !label.is_secret() => Some(name),
+        _ => None,
+    }
+}
+
+fn decl_key(v: &VarInfo) -> (String, u32, u32) {
+    (v.decl.file.clone(), v.decl.start_line, v.decl.start_col)
+}
+
+/// `secret-shadowed` when `name` hides a variable that still holds an unconsumed secret:
+/// the secret would become unreachable without being moved out.
+fn check_shadow(prev: Option<&VarInfo>, name: &Ident, cx: &FnCtx, r: &mut DiagnosticReport) {
+    let Some(v) = prev else {
+        return;
+    };
+    if !v.surely_moved(&[])
+        && holds_live_secret(
+            &cx.resolve(&v.ty),
+            v.label.is_secret(),
+            &mut Vec::new(),
+            &v.moved_fields,
+            cx.defs,
+        )
+    {
+        r.push(Diagnostic::error(
+            "secret-shadowed",
+            format!(
+                "`{}` shadows a variable that still holds an unconsumed secret; move it out first or use another name.",
+                name.name
+            ),
+            name.span.clone(),
+        ));
+    }
+}
+
+fn typecheck_fn(
+    f: &FnDecl,
+    fns: &HashMap<String, FnSig>,
+    defs: &TypeDefs,
+    r: &mut DiagnosticReport,
+) {
+    let generics = generics_of(f);
+    check_generics(f, r);
+    let ret = lower_checked(&f.ret, defs, &generics, "return", r);
+    let current_effects = effects_set_of_fn(f);
+
+    let mut env: HashMap<String, VarInfo> = HashMap::new();
+
+    for (i, p) in f.params.iter().enumerate() {
+        let pt = lower_checked(&p.ty, defs, &generics, "parameter", r);
+        if f.params[..i].iter().any(|q| q.name.name == p.name.name) {
+            r.push(Diagnostic::error(
+                "param-duplicate",
+                format!(
+                    "Duplicate parameter `{}` in `{}`.",
+                    p.name.name, f.name.name
+                ),
+                p.name.span.clone(),
+            ));
+            continue;
+        }
+        env.insert(
+            p.name.name.clone(),
+            VarInfo::new(pt, label_of_type(&p.ty, defs), false, &p.name.span),
+        );
+    }
+
+    let infer = RefCell::new(Infer::default());
+    let ended = RefCell::new(HashSet::new());
+    let unconsumed = RefCell::new(HashSet::new());
+    let cx = FnCtx {
+        current_fn: f,
+        ret: &ret,
+        current_effects: &current_effects,
+        generics: &generics,
+        fns,
+        defs,
+        infer: &infer,
+        ended: &ended,
+        unconsumed: &unconsumed,
+    };
+    typecheck_block(&f.body, &mut env, &cx, r);
+
+    if !block_diverges(&f.body) {
+        for p in &f.params {
+            if let Some(v) = env.get(&p.name.name) {
+                check_consumed(&p.name.name, v, "}", &cx, r);
+            }
+        }
+    }
+
+    if !matches!(ret, Ty::Unit | Ty::Error) && !block_diverges(&f.body) {
+        r.push(Diagnostic::error(
+            "missing-return",
+            format!(
+                "`{}` must return {}, but the end of its body is reachable without a `return`.",
+                f.name.name,
+                written_type(&f.ret, &ret)
+            ),
+            f.body.span.clone(),
+        ));
+    }
+
+    let infer = infer.into_inner();
+    // Each unsolved variable is reported once, at the binding if it has one.
+    let mut reported = HashSet::new();
+    for (t, name) in &infer.bindings {
+        if let Ty::Var(v) = infer.resolve(t) {
+            reported.insert(v);
+            r.push(Diagnostic::error(
+                "type-ambiguous",
+                format!(
+                    "Cannot infer the type of `{}`; add a type annotation.",
+                    name.name
+                ),
+                name.span.clone(),
+            ));
+        }
+    }
+    for (t, span) in &infer.dropped {
+        if let Ty::Var(v) = infer.resolve(t) {
+            if reported.insert(v) {
+                r.push(Diagnostic::error(
+                    "type-ambiguous",
+                    "Cannot infer the type of this expression; bind it with an annotated `let`.",
+                    span.clone(),
+                ));
+            }
+        }
+    }
+}
+
+/// Type parameters must be distinct; `Copy` is the only bound.
+fn check_generics(f: &FnDecl, r: &mut DiagnosticReport) {
+    for (i, g) in f.generics.iter().enumerate() {
+        if f.generics[..i].iter().any(|h| h.name.name == g.name.name) {
+            r.push(Diagnostic::error(
+                "generic-duplicate",
+                format!("Type parameter `{}` is declared twice.", g.name.name),
+                g.span.clone(),
+            ));
+        }
+        for b in &g.bounds {
+            if b.name != "Copy" {
+                r.push(Diagnostic::error(
+                    "generic-unknown-bound",
+                    format!("Unknown bound `{}` (only `Copy` is supported).", b.name),
+                    b.span.clone(),
+                ));
+            }
+        }
+    }
+}
+
+fn typecheck_stmt(
+    s: &Stmt,
+    env: &mut HashMap<String, VarInfo>,
+    cx: &FnCtx,
+    r: &mut DiagnosticReport,
+) {
+    match s {
+        Stmt::Let {
+            name,
+            mutable,
+            ty,
+            expr,
+            ..
+        } => {
+            let rhs = type_of_expr(expr, env, cx, r);
+
+            let (ann_ty, ann_label, declared) = if let Some(ann) = ty {
+                let at = lower_checked(ann, cx.defs, cx.generics, "annotated", r);
+                let label = label_of_type(ann, cx.defs);
+                if !cx.unify(&at, &rhs.ty) {
+                    r.push(Diagnostic::error(
+                        mismatch_code(&at, &rhs.ty, "type-mismatch"),
+                        format!(
+                            "Type mismatch: expected {}, got {}.",
+                            written_type(ann, &at),
+                            ty_name(&rhs.ty)
+                        ),
+                        ann.span().clone(),
+                    ));
+                }
+                let what = format!("`{}`", name.name);
+                check_label_flow(rhs.label, label, &what, expr.span(), cx.defs, r);
+                // An annotation does not declassify: the binding keeps the higher label.
+                (at, label.join(rhs.label), Some(label))
+            } else {
+                // Each unannotated binding gets an inference variable; its uses may pin it down.
+                let t = cx.infer.borrow_mut().fresh();
+                cx.unify(&t, &rhs.ty);
+                cx.infer
+                    .borrow_mut()
+                    .bindings
+                    .push((t.clone(), name.clone()));
+                (cx.resolve(&t), rhs.label, None)
+            };
+
+            check_secret_copy(
+                &rhs,
+                expr,
+                &format!("let {} = ", name.name),
+                ";",
+                env,
+                cx,
+                r,
+            );
+
+            let prev = env.remove(&name.name);
+            check_shadow(prev.as_ref(), name, cx, r);
+            let mut v = VarInfo::new(ann_ty, ann_label, *mutable, &name.span);
+            v.declared = declared;
+            v.from_let = true;
+
+            v.shadowed = prev.map(Box::new);
+            // Bound to a value that already has a diagnostic: not reported again when dropped.
+            if cx.resolve(&rhs.ty) == Ty::Error {
+                cx.unconsumed.borrow_mut().insert(decl_key(&v));
+            }
+
+            env.insert(name.name.clone(), v);
+        }
+
+        Stmt::Assign { name, expr, span } => {
+            let rhs = type_of_expr(expr, env, cx, r);
+
+            let Some(v) = env.get_mut(&name.name) else {
+                r.push(cx.unknown_name(name));
+                return;
+            };
+
+            if !v.mutable {
+                let d = Diagnostic::error(
+                    "assign-immutable",
+                    format!(
+                        "Cannot assign twice to immutable variable `{}`; declare it with `let mut`.",
+                        name.name
+                    ),
+                    span.clone(),
+                );
+                // Only a `let` can become `let mut`.
+                r.push(if v.from_let {
+                    d.with_fix(
+                        format!("let {} ", name.name),
+                        format!("let mut {} ", name.name),
+                    )
+                } else {
+                    d
+                });
+            }
+
+            v.ty = cx.resolve(&v.ty);
+            if !cx.unify(&v.ty, &rhs.ty) {
+                r.push(Diagnostic::error(
+                    mismatch_code(&v.ty, &rhs.ty, "type-mismatch"),
+                    format!(
+                        "Type mismatch: `{}` is {}, got {}.",
+                        name.name,
+                        ty_name(&v.ty),
+                        ty_name(&rhs.ty)
+                    ),
+                    expr.span().clone(),
+                ));
+            }
+
+            // Overwriting a live secret would silently drop key material.
+            if !v.surely_moved(&[])
+                && holds_live_secret(
+                    &v.ty,
+                    v.label.is_secret(),
+                    &mut Vec::new(),
+                    &v.moved_fields,
+                    cx.defs,
+                )
+            {
+                r.push(
+                    Diagnostic::error(
+                        "secret-overwrite",
+                        format!(
+                            "Assignment would overwrite `{}`, which still holds an unconsumed secret; move it out or wipe it first.",
+                            name.name
+                        ),
+                        span.clone(),
+                    )
+                    .with_fix(
+                        format!("{} = ", name.name),
+                        format!("wipe({});\n{} = ", name.name, name.name),
+                    ),
+                );
+            }
+
+            if let Some(declared) = v.declared {
+                let what = format!("`{}`", name.name);
+                check_label_flow(rhs.label, declared, &what, expr.span(), cx.defs, r);
+            }
+
+            // Assignment revives a moved variable; secrecy only ever grows.
+            v.moved = None;
+            v.moved_fields.clear();
+            v.label = v.label.join(rhs.label);
+
+            check_secret_copy(&rhs, expr, &format!("{} = ", name.name), ";", env, cx, r);
+        }
+
+        Stmt::Return { expr, span } => {
+            match (cx.ret, expr) {
+                (Ty::Unit, None) => {}
+                (Ty::Unit, Some(_)) => {
+                    r.push(Diagnostic::error(
+                        "return-mismatch",
+                        "Return value provided but function returns Unit.",
+                        span.clone(),
+                    ));
+                }
+                (rt, None) => {
+                    r.push(Diagnostic::error(
+                        "return-mismatch",
+                        format!(
+                            "Missing return value; expected {}.",
+                            written_type(&cx.current_fn.ret, rt)
+                        ),
+                        span.clone(),
+                    ));
+                }
+                (rt, Some(e)) => {
+                    let et = type_of_expr(e, env, cx, r);
+                    if !cx.unify(rt, &et.ty) {
+                        r.push(Diagnostic::error(
+                            mismatch_code(rt, &et.ty, "return-mismatch"),
+                            format!(
+                                "Return type mismatch: expected {}, got {}.",
+                                written_type(&cx.current_fn.ret, rt),
+                                ty_name(&et.ty)
+                            ),
+                            e.span().clone(),
+                        ));
+                    }
+                    let ret_label = label_of_type(&cx.current_fn.ret, cx.defs);
+                    if et.label.is_secret() && !ret_label.is_secret() {
+                        r.push(
+                            Diagnostic::error(
+                                "secret-leak-return",
+                                format!(
+                                    "`{}` returns a secret value but its return type is not secret.",
+                                    cx.current_fn.name.name
+                                ),
+                                e.span().clone(),
+                            )
+                            .with_fix(
+                                format!("-> {}", written_type(&cx.current_fn.ret, rt)),
+                                format!("-> secret {}", written_type(&cx.current_fn.ret, rt)),
+                            ),
+                        );
+                    } else {
+                        let what = format!("the return value of `{}`", cx.current_fn.name.name);
+                        check_label_flow(et.label, ret_label, &what, e.span(), cx.defs, r);
+                    }
+                }
+            };
+
+            // Returning a variable hands it to the caller.
+            if let Some((var, path)) = expr.as_ref().and_then(place_of) {
+                let path = path.into_iter().map(|f| f.name.clone()).collect();
+                if let Some(v) = env.get_mut(&var.name) {
+                    v.consume(path, &var.span);
+                }
+            }
+            // Every binding in scope leaves it here.
+            let mut live: Vec<(&String, &VarInfo)> = env.iter().collect();
+            live.sort_by_key(|(_, v)| (v.decl.start_line, v.decl.start_col));
+            for (n, v) in live {
+                check_consumed(n, v, "return", cx, r);
+            }
+        }
+
+        Stmt::If {
+            cond,
+            then_blk,
+            else_blk,
+            ..
+        } => {
+            let ct = type_of_expr(cond, env, cx, r);
+
+            if !cx.unify(&Ty::Bool, &ct.ty) {
+                r.push(Diagnostic::error(
+                    "if-cond-not-bool",
+                    format!("If condition must be Bool, got {}.", ty_name(&ct.ty)),
+                    cond.span().clone(),
+                ));
+            }
+
+            if ct.label.is_secret() {
+                r.push(Diagnostic::error(
+                    "secret-branch",
+                    "Branching on secret data is forbidden.",
+                    cond.span().clone(),
+                ));
+                let mut returns = Vec::new();
+                returns_in(then_blk, &mut returns);
+                if let Some(eb) = else_blk {
+                    returns_in(eb, &mut returns);
+                }
+                for sp in returns {
+                    check_ct(cx, "this `return` depends on secret data", sp, r);
+                }
+            }
+
+            // Each branch starts from the state before the `if`; they meet again after it.
+            let mut paths = Vec::new();
+            for blk in std::iter::once(then_blk).chain(else_blk) {
+                let mut branch = env.clone();
+                typecheck_block(blk, &mut branch, cx, r);
+                if !block_diverges(blk) {
+                    paths.push(branch);
+                }
+            }
+            if else_blk.is_none() {
+                paths.push(env.clone());
+            }
+            *env = join_paths(env, &paths);
+        }
+
+        Stmt::For {
+            var,
+            start,
+            end,
+            body,
+            ..
+        } => {
+            for bound in [start, end] {
+                let bt = type_of_expr(bound, env, cx, r);
+                if !cx.unify(&Ty::Int, &bt.ty) {
+                    r.push(Diagnostic::error(
+                        "type-mismatch",
+                        format!("Loop bound must be Int, got {}.", ty_name(&bt.ty)),
+                        bound.span().clone(),
+                    ));
+                }
+                // Iteration counts must not depend on secrets (timing) nor on runtime data
+                // (determinism): bounds are compile-time constants.
+                if bt.label.is_secret() {
+                    r.push(Diagnostic::error(
+                        "secret-loop-bound",
+                        "Loop bounds must not depend on secret data.",
+                        bound.span().clone(),
+                    ));
+                } else if cx.resolve(&bt.ty) == Ty::Int && const_int(bound, cx.defs).is_none() {
+                    r.push(Diagnostic::error(
+                        "loop-bound-not-constant",
+                        "Loop bounds must be compile-time constants.",
+                        bound.span().clone(),
+                    ));
+                }
+            }
+
+            // The loop variable is a public Int visible in the body only.
+            check_shadow(env.get(&var.name), var, cx, r);
+            let outer = env.insert(
+                var.name.clone(),
+                VarInfo::new(Ty::Int, Label::PUBLIC, false, &var.span),
+            );
+
+            let live_before: BTreeSet<String> = env
+                .iter()
+                .filter(|(_, v)| v.first_move().is_none())
+                .map(|(n, _)| n.clone())
+                .collect();
+
+            let before = env.clone();
+            typecheck_block(body, env, cx, r);
+
+            // A variable live before the loop but moved at the end of the body would be
+            // used after move on the next iteration (unless the body re-assigned it).
+            for n in &live_before {
+                if n == &var.name {
+                    continue;
+                }
+                if let Some(at) = env.get(n).and_then(|v| v.first_move().cloned()) {
+                    r.push(Diagnostic::error(
+                        "move-in-loop",
+                        format!(
+                            "`{}` is declared outside the loop and moved inside it; the next iteration would use it after move. Re-assign it before the iteration ends.",
+                            n
+                        ),
+                        at,
+                    ));
+                }
+            }
+
+            // An empty range skips the body: then the state before the loop also reaches
+            // its end, as for an `if` without `else`.
+            let runs = matches!(
+                (const_int(start, cx.defs), const_int(end, cx.defs)),
+                (Some(a), Some(b)) if a < b
+            );
+            if !runs {
+                let mut paths = vec![before.clone()];
+                if !block_diverges(body) {
+                    paths.push(env.clone());
+                }
+                *env = join_paths(&before, &paths);
+            }
+
+            match outer {
+                Some(v) => {
+                    env.insert(var.name.clone(), v);
+                }
+                None => {
+                    env.remove(&var.name);
+                }
+            }
+        }
 
-    for s in &f.body.stmts {
-        typecheck_stmt(s, &mut env, &ret, &current_effects, f, fn_effects, r);
+        Stmt::Expr { expr, .. } => {
+            let et = type_of_expr(expr, env, cx, r);
+            if let Ty::Var(_) = cx.resolve(&et.ty) {
+                cx.infer
+                    .borrow_mut()
+                    .dropped
+                    .push((et.ty, expr.span().clone()));
+            }
+        }
+
+        Stmt::Match {
+            scrutinee, arms, ..
+        } => typecheck_match(scrutinee, arms, env, cx, r),
+
+        // Parse errors were reported already; nothing to check.
+        Stmt::Error { .. } => {}
     }
 }
 
-fn typecheck_stmt(
-    s: &Stmt,
+/// Scrutinee rules mirror `if`: an enum or Bool, never secret. Arms must cover every
+/// variant (or end with `_`); bindings are visible in their arm only.
+fn typecheck_match(
+    scrutinee: &Expr,
+    arms: &[MatchArm],
     env: &mut HashMap<String, VarInfo>,
-    ret: &Ty,
-    current_effects: &BTreeSet<Effect>,
-    current_fn: &FnDecl,
-    fn_effects: &HashMap<String, BTreeSet<Effect>>,
+    cx: &FnCtx,
     r: &mut DiagnosticReport,
 ) {
-    match s {
-        Stmt::Let { name, ty, expr, .. } => {
-            let rhs = type_of_expr(expr, env, current_effects, current_fn, fn_effects, r);
+    let mut st = type_of_expr(scrutinee, env, cx, r);
+    st.ty = cx.resolve(&st.ty);
+
+    let all: Vec<String> = match &st.ty {
+        Ty::Enum(n) => cx.defs.enums[n]
+            .variants
+            .iter()
+            .map(|v| format!("{}::{}", n, v.name))
+            .collect(),
+        Ty::Bool => vec!["true".into(), "false".into()],
+        Ty::Error => Vec::new(),
+        Ty::Var(_) => {
+            r.push(Diagnostic::error(
+                "type-ambiguous",
+                "Cannot infer the type of the match scrutinee; add a type annotation.",
+                scrutinee.span().clone(),
+            ));
+            Vec::new()
+        }
+        other => {
+            r.push(Diagnostic::error(
+                "type-mismatch",
+                format!(
+                    "Match scrutinee must be an enum or Bool, got {}.",
+                    ty_name(other)
+                ),
+                scrutinee.span().clone(),
+            ));
+            Vec::new()
+        }
+    };
 
-            let (ann_ty, ann_secret) = if let Some(ann) = ty {
-                let at = lower_type(ann);
-                let sec = is_secret_type(ann);
-                if at == Ty::Unknown {
-                    r.push(Diagnostic::error(
-                        "type-unknown",
-                        "Unknown annotated type (Candy supports Int|Bool|Unit and secret wrappers).",
-                        ann.span().clone(),
-                    ));
-                } else if rhs.ty != Ty::Unknown && rhs.ty != at {
+    if st.label.is_secret() {
+        r.push(Diagnostic::error(
+            "secret-branch",
+            "Branching on secret data is forbidden.",
+            scrutinee.span().clone(),
+        ));
+        let mut returns = Vec::new();
+        for arm in arms {
+            returns_in(&arm.body, &mut returns);
+        }
+        for sp in returns {
+            check_ct(cx, "this `return` depends on secret data", sp, r);
+        }
+    }
+
+    // Binding a move-only payload takes it out of the scrutinee, so it must be moved.
+    let binds_linear = arms.iter().any(|arm| match &arm.pattern {
+        Pattern::Variant { path, bindings, .. } => {
+            cx.defs.variant(&path.name).is_some_and(|(_, v)| {
+                v.payload
+                    .iter()
+                    .zip(bindings)
+                    .any(|((t, l), b)| b.name != "_" && is_linear(t, l.is_secret(), cx.defs))
+            })
+        }
+        _ => false,
+    });
+    if binds_linear {
+        check_secret_copy(&st, scrutinee, "match ", " {", env, cx, r);
+    }
+
+    let mut covered: Vec<String> = Vec::new();
+    let mut has_wildcard = false;
+    let mut paths = Vec::new();
+    for arm in arms {
+        let (key, bindings): (Option<String>, Vec<(&Ident, Ty, Label)>) = match &arm.pattern {
+            Pattern::Wildcard { .. } => (None, Vec::new()),
+            Pattern::BoolLit { value, span } => {
+                if !matches!(st.ty, Ty::Bool | Ty::Error) {
                     r.push(Diagnostic::error(
-                        "type-mismatch",
+                        "pattern-mismatch",
                         format!(
-                            "Type mismatch: expected {}, got {}.",
-                            ty_name(&at),
-                            ty_name(&rhs.ty)
+                            "Pattern `{}` does not match type {}.",
+                            value,
+                            ty_name(&st.ty)
                         ),
-                        ann.span().clone(),
+                        span.clone(),
                     ));
                 }
-                (at, sec)
-            } else {
-                (rhs.ty.clone(), rhs.is_secret)
+                (Some(value.to_string()), Vec::new())
+            }
+            Pattern::Variant {
+                path,
+                bindings,
+                span,
+            } => match cx.defs.variant(&path.name) {
+                Some((enum_name, v)) => {
+                    if !matches!(&st.ty, Ty::Enum(n) if n == enum_name) && st.ty != Ty::Error {
+                        r.push(Diagnostic::error(
+                            "pattern-mismatch",
+                            format!(
+                                "Pattern `{}` does not match type {}.",
+                                path.name,
+                                ty_name(&st.ty)
+                            ),
+                            span.clone(),
+                        ));
+                    }
+                    if bindings.len() != v.payload.len() {
+                        r.push(Diagnostic::error(
+                            "pattern-arity",
+                            format!(
+                                "Variant `{}` has {} payload value(s), pattern binds {}.",
+                                path.name,
+                                v.payload.len(),
+                                bindings.len()
+                            ),
+                            span.clone(),
+                        ));
+                    }
+                    let bound = bindings
+                        .iter()
+                        .zip(&v.payload)
+                        .map(|(b, (t, l))| (b, t.clone(), l.join(st.label)))
+                        .collect();
+                    (Some(path.name.clone()), bound)
+                }
+                None => {
+                    r.push(Diagnostic::error(
+                        "name-unknown",
+                        format!("Unknown enum variant `{}`.", path.name),
+                        path.span.clone(),
+                    ));
+                    (None, Vec::new())
+                }
+            },
+        };
+
+        let redundant = has_wildcard
+            || match &key {
+                Some(k) => covered.contains(k),
+                None => !all.is_empty() && all.iter().all(|v| covered.contains(v)),
             };
+        if redundant {
+            r.push(Diagnostic::warning(
+                "match-unreachable-arm",
+                "This arm is unreachable: earlier arms already cover it.",
+                arm.pattern.span().clone(),
+            ));
+        }
+        match key {
+            Some(k) => covered.push(k),
+            None if matches!(arm.pattern, Pattern::Wildcard { .. }) => has_wildcard = true,
+            None => {}
+        }
 
-            if rhs.is_secret && rhs.copied_secret {
-                r.push(
-                    Diagnostic::error(
-                        "secret-copy",
-                        format!(
-                            "Secret value `{}` cannot be copied. Use move({}) to transfer ownership.",
-                            rhs.name_hint.as_deref().unwrap_or("<secret>"),
-                            rhs.name_hint.as_deref().unwrap_or("<x>")
-                        ),
-                        expr.span().clone(),
-                    )
-                    .with_fix(
-                        format!(
-                            "let {} = {};",
-                            name.name,
-                            rhs.name_hint.clone().unwrap_or("x".into())
-                        ),
-                        format!(
-                            "let {} = move({});",
-                            name.name,
-                            rhs.name_hint.clone().unwrap_or("x".into())
-                        ),
-                    ),
-                );
+        // Each arm starts from the state after the scrutinee; the arms meet again after
+        // the `match`, where the bindings are out of scope again.
+        let mut arm_env = env.clone();
+        let mut bound = Vec::new();
+        for (b, ty, label) in bindings {
+            if b.name == "_" {
+                continue;
             }
-
-            env.insert(
-                name.name.clone(),
-                VarInfo {
-                    ty: ann_ty,
-                    is_secret: ann_secret,
-                    moved: false,
-                },
-            );
+            check_shadow(arm_env.get(&b.name), b, cx, r);
+            arm_env.insert(b.name.clone(), VarInfo::new(ty, label, false, &b.span));
+            bound.push(&b.name);
         }
 
-        Stmt::Return { expr, span } => match (ret, expr) {
-            (Ty::Unit, None) => {}
-            (Ty::Unit, Some(_)) => {
-                r.push(Diagnostic::error(
-                    "return-mismatch",
-                    "Return value provided but function returns Unit.",
-                    span.clone(),
-                ));
+        typecheck_block(&arm.body, &mut arm_env, cx, r);
+
+        if !block_diverges(&arm.body) {
+            for n in &bound {
+                check_consumed(n, &arm_env[*n], "}", cx, r);
             }
-            (rt, None) => {
-                r.push(Diagnostic::error(
-                    "return-mismatch",
-                    format!("Missing return value; expected {}.", ty_name(rt)),
-                    span.clone(),
-                ));
+            for n in bound {
+                if let Some(outer) = env.get(n) {
+                    arm_env.insert(n.clone(), outer.clone());
+                }
             }
-            (rt, Some(e)) => {
-                let et = type_of_expr(e, env, current_effects, current_fn, fn_effects, r);
-                if et.ty != Ty::Unknown && et.ty != *rt {
-                    r.push(Diagnostic::error(
-                        "return-mismatch",
+            paths.push(arm_env);
+        }
+    }
+    *env = join_paths(env, &paths);
+
+    let missing: Vec<&str> = all
+        .iter()
+        .filter(|v| !covered.contains(v))
+        .map(String::as_str)
+        .collect();
+    if !has_wildcard && !missing.is_empty() {
+        r.push(Diagnostic::error(
+            "match-non-exhaustive",
+            format!(
+                "Match on {} is not exhaustive; missing: {}. Add the arms or a `_` arm.",
+                ty_name(&st.ty),
+                missing.join(", ")
+            ),
+            scrutinee.span().clone(),
+        ));
+    }
+}
+
+/// The Int value of a compile-time constant expression, if it has one.
+fn const_int(e: &Expr, defs: &TypeDefs) -> Option<i64> {
+    let mut lookup = |id: &Ident| {
+        defs.consts
+            .get(&id.name)
+            .and_then(|c| c.value.clone())
+            .ok_or(NotConst::Poisoned)
+    };
+    match eval_const(e, &mut lookup) {
+        Ok(ConstVal::Int(v)) => Some(v),
+        _ => None,
+    }
+}
+
+/// Why an expression has no compile-time value.
+enum NotConst {
+    /// To be reported: code, message and the offending sub-expression.
+    At(&'static str, String, Span),
+    /// Reads a constant whose own initializer was rejected.
+    Poisoned,
+}
+
+/// Folds literals, operators and named constants (looked up through `var`).
+fn eval_const(
+    e: &Expr,
+    var: &mut dyn FnMut(&Ident) -> Result<ConstVal, NotConst>,
+) -> Result<ConstVal, NotConst> {
+    use ConstVal::{Bool, Int};
+    let overflow = |span: &Span| {
+        NotConst::At(
+            "const-not-constant",
+            "Constant arithmetic overflows or divides by zero.".into(),
+            span.clone(),
+        )
+    };
+    match e {
+        Expr::IntLit { value, .. } => Ok(Int(*value)),
+        Expr::BoolLit { value, .. } => Ok(Bool(*value)),
+        Expr::StrLit { value, .. } => Ok(ConstVal::Str(value.clone())),
+        Expr::Var { name, .. } => var(name),
+        Expr::Unary { op, expr, span } => match (op, eval_const(expr, var)?) {
+            (UnOp::Neg, Int(v)) => v.checked_neg().map(Int).ok_or_else(|| overflow(span)),
+            (UnOp::Not, Bool(b)) => Ok(Bool(!b)),
+            (op, v) => Err(NotConst::At(
+                "op-type-mismatch",
+                format!(
+                    "Operator `{}` cannot be applied to {}.",
+                    op.as_str(),
+                    ty_name(&v.ty())
+                ),
+                span.clone(),
+            )),
+        },
+        Expr::Binary { op, lhs, rhs, span } => {
+            let (a, b) = (eval_const(lhs, var)?, eval_const(rhs, var)?);
+            let v = match (op, &a, &b) {
+                (BinOp::Add, Int(x), Int(y)) => x.checked_add(*y).map(Int),
+                (BinOp::Sub, Int(x), Int(y)) => x.checked_sub(*y).map(Int),
+                (BinOp::Mul, Int(x), Int(y)) => x.checked_mul(*y).map(Int),
+                (BinOp::Div, Int(x), Int(y)) => x.checked_div(*y).map(Int),
+                (BinOp::Rem, Int(x), Int(y)) => x.checked_rem(*y).map(Int),
+                (BinOp::Lt, Int(x), Int(y)) => Some(Bool(x < y)),
+                (BinOp::Le, Int(x), Int(y)) => Some(Bool(x <= y)),
+                (BinOp::Gt, Int(x), Int(y)) => Some(Bool(x > y)),
+                (BinOp::Ge, Int(x), Int(y)) => Some(Bool(x >= y)),
+                (BinOp::And, Bool(x), Bool(y)) => Some(Bool(*x && *y)),
+                (BinOp::Or, Bool(x), Bool(y)) => Some(Bool(*x || *y)),
+                (BinOp::Eq | BinOp::Ne, a, b) if a.ty() == b.ty() => {
+                    Some(Bool((a == b) == (*op == BinOp::Eq)))
+                }
+                _ => {
+                    return Err(NotConst::At(
+                        "op-type-mismatch",
                         format!(
-                            "Return type mismatch: expected {}, got {}.",
-                            ty_name(rt),
-                            ty_name(&et.ty)
+                            "Operator `{}` cannot be applied to {} and {}.",
+                            op.as_str(),
+                            ty_name(&a.ty()),
+                            ty_name(&b.ty())
                         ),
-                        e.span().clone(),
-                    ));
+                        span.clone(),
+                    ))
                 }
-            }
-        },
+            };
+            v.ok_or_else(|| overflow(span))
+        }
+        Expr::Call { callee, span, .. } => Err(NotConst::At(
+            "const-not-constant",
+            format!(
+                "A constant cannot call `{}`; only literals, operators and other constants are allowed.",
+                callee.name
+            ),
+            span.clone(),
+        )),
+        other => Err(NotConst::At(
+            "const-not-constant",
+            "Only literals, operators and other constants are allowed in a constant.".into(),
+            other.span().clone(),
+        )),
+    }
+}
 
-        Stmt::If {
-            cond,
-            then_blk,
-            else_blk,
-            ..
-        } => {
-            let ct = type_of_expr(cond, env, current_effects, current_fn, fn_effects, r);
+/// Evaluates every constant on demand, so a constant may use others declared after it
+/// (or in another module).
+fn collect_consts(
+    p: &Program,
+    defs: &TypeDefs,
+    r: &mut DiagnosticReport,
+) -> HashMap<String, ConstInfo> {
+    let mut ev = ConstEval {
+        decls: HashMap::new(),
+        values: HashMap::new(),
+        visiting: Vec::new(),
+        defs,
+    };
+    for d in &p.consts {
+        if ev.decls.contains_key(d.name.name.as_str()) {
+            r.push(Diagnostic::error(
+                "const-duplicate",
+                format!("Duplicate constant `{}`.", d.name.name),
+                d.name.span.clone(),
+            ));
+            continue;
+        }
+        ev.decls.insert(&d.name.name, d);
+    }
 
-            if ct.ty != Ty::Bool && ct.ty != Ty::Unknown {
-                r.push(Diagnostic::error(
-                    "if-cond-not-bool",
-                    format!("If condition must be Bool, got {}.", ty_name(&ct.ty)),
-                    cond.span().clone(),
+    let mut out = HashMap::new();
+    for d in &p.consts {
+        if out.contains_key(&d.name.name) {
+            continue;
+        }
+        let ty = lower_checked(&d.ty, defs, &[], "constant", r);
+        let value = ev.value_of(&d.name.name, r);
+        out.insert(
+            d.name.name.clone(),
+            ConstInfo {
+                ty,
+                label: label_of_type(&d.ty, defs),
+                value,
+            },
+        );
+    }
+    out
+}
+
+struct ConstEval<'a> {
+    decls: HashMap<&'a str, &'a ConstDecl>,
+    values: HashMap<String, Option<ConstVal>>,
+    /// Constants being evaluated, to catch self-reference.
+    visiting: Vec<String>,
+    defs: &'a TypeDefs,
+}
+
+impl ConstEval<'_> {
+    fn value_of(&mut self, name: &str, r: &mut DiagnosticReport) -> Option<ConstVal> {
+        if let Some(v) = self.values.get(name) {
+            return v.clone();
+        }
+        let d = self.decls[name];
+        self.visiting.push(name.to_string());
+        let res = eval_const(&d.expr, &mut |id: &Ident| {
+            if self.visiting.contains(&id.name) {
+                return Err(NotConst::At(
+                    "const-not-constant",
+                    format!("Constant `{}` depends on itself.", id.name),
+                    id.span.clone(),
                 ));
             }
-
-            if ct.is_secret {
-                r.push(Diagnostic::error(
-                    "secret-branch",
-                    "Branching on secret data is forbidden.",
-                    cond.span().clone(),
+            if !self.decls.contains_key(id.name.as_str()) {
+                return Err(NotConst::At(
+                    "const-not-constant",
+                    format!(
+                        "`{}` is not a constant; a constant cannot read variables.",
+                        id.name
+                    ),
+                    id.span.clone(),
                 ));
             }
-
-            for st in &then_blk.stmts {
-                typecheck_stmt(st, env, ret, current_effects, current_fn, fn_effects, r);
+            self.value_of(&id.name, r).ok_or(NotConst::Poisoned)
+        });
+        self.visiting.pop();
+
+        let want = lower_type(&d.ty, self.defs, &[]);
+        let value = match res {
+            Ok(v) if want == Ty::Error || v.ty() == want => Some(v),
+            Ok(v) => {
+                r.push(Diagnostic::error(
+                    "type-mismatch",
+                    format!(
+                        "Type mismatch: expected {}, got {}.",
+                        written_type(&d.ty, &want),
+                        ty_name(&v.ty())
+                    ),
+                    d.expr.span().clone(),
+                ));
+                None
             }
-
-            if let Some(eb) = else_blk {
-                for st in &eb.stmts {
-                    typecheck_stmt(st, env, ret, current_effects, current_fn, fn_effects, r);
-                }
+            Err(NotConst::At(code, msg, span)) => {
+                r.push(Diagnostic::error(code, msg, span));
+                None
             }
-        }
+            Err(NotConst::Poisoned) => None,
+        };
+        self.values.insert(name.to_string(), value.clone());
+        value
+    }
+}
 
-        Stmt::Expr { expr, .. } => {
-            let _ = type_of_expr(expr, env, current_effects, current_fn, fn_effects, r);
-        }
+/// `secret-copy` when `rhs` is a plain read of a secret (or secret-holding) place.
+/// `target` and `term` are the source text around the expression (`let x = ` ... `;`,
+/// `field: ` ... ``), used for the fix hint. The copied variable is not reported again as
+/// `secret-not-consumed`: the suggested `move` would consume it.
+#[allow(clippy::too_many_arguments)]
+fn check_secret_copy(
+    rhs: &ExprTy,
+    expr: &Expr,
+    target: &str,
+    term: &str,
+    env: &HashMap<String, VarInfo>,
+    cx: &FnCtx,
+    r: &mut DiagnosticReport,
+) {
+    if !rhs.copied_secret {
+        return;
+    }
+    if let Some(v) = place_of(expr).and_then(|(var, _)| env.get(&var.name)) {
+        cx.unconsumed.borrow_mut().insert(decl_key(v));
     }
+    let shown = rhs.name_hint.as_deref().unwrap_or("<secret>");
+    let what = match generic_param(&cx.resolve(&rhs.ty), rhs.label) {
+        Some(t) => format!(
+            "Value `{}` of type parameter `{}` may be secret and cannot be copied.",
+            shown, t
+        ),
+        None => format!("Secret value `{}` cannot be copied.", shown),
+    };
+    let d = Diagnostic::error(
+        "secret-copy",
+        format!(
+            "{} Use move({}) to transfer ownership.",
+            what,
+            rhs.name_hint.as_deref().unwrap_or("<x>")
+        ),
+        expr.span().clone(),
+    );
+    // The fix wraps a plain variable or field read in `move(...)`.
+    if place_of(expr).is_none() {
+        r.push(d);
+        return;
+    }
+    r.push(d.with_fix(
+        format!(
+            "{}{}{}",
+            target,
+            rhs.name_hint.clone().unwrap_or("x".into()),
+            term
+        ),
+        format!(
+            "{}move({}){}",
+            target,
+            rhs.name_hint.clone().unwrap_or("x".into()),
+            term
+        ),
+    ));
 }
 
 #[derive(Debug, Clone)]
 struct ExprTy {
     ty: Ty,
-    is_secret: bool,
+    label: Label,
     copied_secret: bool,
     name_hint: Option<String>,
 }
@@ -372,13 +2456,9 @@ fn require_effects_for_call(
     call_site: Span,
     current_effects: &BTreeSet<Effect>,
     current_fn: &FnDecl,
-    fn_effects: &HashMap<String, BTreeSet<Effect>>,
+    needed: &BTreeSet<Effect>,
     r: &mut DiagnosticReport,
 ) {
-    let Some(needed) = fn_effects.get(callee) else {
-        return;
-    };
-
     let mut missing = BTreeSet::new();
     for e in needed {
         if !current_effects.contains(e) {
@@ -414,112 +2494,344 @@ fn require_effects_for_call(
 fn type_of_expr(
     e: &Expr,
     env: &mut HashMap<String, VarInfo>,
-    current_effects: &BTreeSet<Effect>,
-    current_fn: &FnDecl,
-    fn_effects: &HashMap<String, BTreeSet<Effect>>,
+    cx: &FnCtx,
     r: &mut DiagnosticReport,
 ) -> ExprTy {
     match e {
         Expr::IntLit { .. } => ExprTy {
             ty: Ty::Int,
-            is_secret: false,
+            label: Label::PUBLIC,
             copied_secret: false,
             name_hint: None,
         },
         Expr::BoolLit { .. } => ExprTy {
             ty: Ty::Bool,
-            is_secret: false,
+            label: Label::PUBLIC,
             copied_secret: false,
             name_hint: None,
         },
         Expr::StrLit { .. } => ExprTy {
-            ty: Ty::Unknown,
-            is_secret: false,
+            ty: Ty::Str,
+            label: Label::PUBLIC,
             copied_secret: false,
             name_hint: None,
         },
 
-        Expr::Var { name, .. } => match env.get(&name.name) {
-            Some(v) => {
-                if v.moved {
+        Expr::Var { name, .. } => match env.get(&name.name) {
+            Some(v) => {
+                if !check_place_live(v, name, &[], &name.span, cx, r) {
+                    return ExprTy {
+                        ty: Ty::Error,
+                        label: v.label,
+                        copied_secret: false,
+                        name_hint: Some(name.name.clone()),
+                    };
+                }
+
+                let ty = cx.resolve(&v.ty);
+                ExprTy {
+                    copied_secret: is_linear(&ty, v.label.is_secret(), cx.defs),
+                    ty,
+                    label: v.label,
+                    name_hint: Some(name.name.clone()),
+                }
+            }
+            None => {
+                if let Some(c) = cx.defs.consts.get(&name.name) {
+                    return ExprTy {
+                        ty: c.ty.clone(),
+                        label: c.label,
+                        copied_secret: false,
+                        name_hint: Some(name.name.clone()),
+                    };
+                }
+                if let Some((enum_name, v)) = cx.defs.variant(&name.name) {
+                    if !v.payload.is_empty() {
+                        r.push(Diagnostic::error(
+                            "call-arity",
+                            format!(
+                                "Variant `{}` expects {} argument(s).",
+                                name.name,
+                                v.payload.len()
+                            ),
+                            name.span.clone(),
+                        ));
+                    }
+                    return ExprTy {
+                        ty: Ty::Enum(enum_name.to_string()),
+                        label: Label::PUBLIC,
+                        copied_secret: false,
+                        name_hint: None,
+                    };
+                }
+                r.push(cx.unknown_name(name));
+                ExprTy {
+                    ty: Ty::Error,
+                    label: Label::PUBLIC,
+                    copied_secret: false,
+                    name_hint: Some(name.name.clone()),
+                }
+            }
+        },
+
+        Expr::Move { name, fields, .. } => match env.get_mut(&name.name) {
+            Some(v) => {
+                let path: Vec<String> = fields.iter().map(|f| f.name.clone()).collect();
+                let hint = place_name(&name.name, &path);
+                if !check_place_live(v, name, &path, &name.span, cx, r) {
+                    return ExprTy {
+                        ty: Ty::Error,
+                        label: v.label,
+                        copied_secret: false,
+                        name_hint: Some(hint),
+                    };
+                }
+
+                let (mut ty, mut label) = (cx.resolve(&v.ty), v.label);
+                for f in fields {
+                    (ty, label) = field_of(&ty, label, f, cx.defs, r);
+                }
+
+                v.consume(path, &name.span);
+
+                ExprTy {
+                    ty,
+                    label,
+                    copied_secret: false,
+                    name_hint: Some(hint),
+                }
+            }
+            None => {
+                r.push(cx.unknown_name(name));
+                ExprTy {
+                    ty: Ty::Error,
+                    label: Label::PUBLIC,
+                    copied_secret: false,
+                    name_hint: Some(name.name.clone()),
+                }
+            }
+        },
+
+        Expr::Field { base, field, span } => {
+            // A read of a variable's field: subject to partial-move tracking.
+            if let Some((var, fields)) = place_of(e) {
+                if let Some(v) = env.get(&var.name) {
+                    let path: Vec<String> = fields.iter().map(|f| f.name.clone()).collect();
+                    let hint = place_name(&var.name, &path);
+                    if !check_place_live(v, var, &path, span, cx, r) {
+                        return ExprTy {
+                            ty: Ty::Error,
+                            label: v.label,
+                            copied_secret: false,
+                            name_hint: Some(hint),
+                        };
+                    }
+                    let (mut ty, mut label) = (cx.resolve(&v.ty), v.label);
+                    for f in fields {
+                        (ty, label) = field_of(&ty, label, f, cx.defs, r);
+                    }
+                    return ExprTy {
+                        copied_secret: is_linear(&ty, label.is_secret(), cx.defs),
+                        ty,
+                        label,
+                        name_hint: Some(hint),
+                    };
+                }
+            }
+
+            let bt = type_of_expr(base, env, cx, r);
+            let (ty, label) = field_of(&cx.resolve(&bt.ty), bt.label, field, cx.defs, r);
+            ExprTy {
+                ty,
+                label,
+                copied_secret: false,
+                name_hint: None,
+            }
+        }
+
+        Expr::Index { base, index, .. } => {
+            let bt = type_of_expr(base, env, cx, r);
+            let len = bytes_len(&cx.resolve(&bt.ty), base, r);
+            let it = check_array_bound(index, len, false, env, cx, r);
+            // A byte read out of an array is a derived value, like an operator result.
+            ExprTy {
+                ty: Ty::Int,
+                label: bt.label.join(it.label),
+                copied_secret: false,
+                name_hint: None,
+            }
+        }
+
+        Expr::Slice {
+            base, start, end, ..
+        } => {
+            let bt = type_of_expr(base, env, cx, r);
+            let len = bytes_len(&cx.resolve(&bt.ty), base, r);
+            let _ = check_array_bound(start, len, true, env, cx, r);
+            let _ = check_array_bound(end, len, true, env, cx, r);
+            let ty = match (const_int(start, cx.defs), const_int(end, cx.defs)) {
+                (Some(a), Some(b)) if a > b => {
+                    r.push(Diagnostic::error(
+                        "index-out-of-bounds",
+                        format!("Slice start {} is after its end {}.", a, b),
+                        start.span().clone(),
+                    ));
+                    Ty::Error
+                }
+                (Some(a), Some(b)) if len.is_none_or(|n| b as u64 <= n) && a >= 0 => {
+                    Ty::Bytes((b - a) as u64)
+                }
+                _ => Ty::Error,
+            };
+            // Slicing copies the bytes: a slice of a move-only array must come from `move(..)`.
+            ExprTy {
+                ty,
+                label: bt.label,
+                copied_secret: bt.copied_secret,
+                name_hint: bt.name_hint,
+            }
+        }
+
+        Expr::ArrayLit { elems, .. } => {
+            let mut label = Label::PUBLIC;
+            for el in elems {
+                label = label.join(check_byte_elem(el, env, cx, r));
+            }
+            ExprTy {
+                ty: Ty::Bytes(elems.len() as u64),
+                label,
+                copied_secret: false,
+                name_hint: None,
+            }
+        }
+
+        Expr::ArrayRepeat { elem, count, .. } => {
+            let label = check_byte_elem(elem, env, cx, r);
+            let ct = type_of_expr(count, env, cx, r);
+            let ty = match const_int(count, cx.defs) {
+                Some(n) if n >= 0 && !ct.label.is_secret() => Ty::Bytes(n as u64),
+                _ => {
                     r.push(Diagnostic::error(
-                        "use-after-move",
-                        format!("Use of `{}` after it was moved.", name.name),
-                        name.span.clone(),
+                        "length-not-constant",
+                        "Array length must be a non-negative compile-time constant Int.",
+                        count.span().clone(),
                     ));
-                    return ExprTy {
-                        ty: Ty::Unknown,
-                        is_secret: v.is_secret,
-                        copied_secret: false,
-                        name_hint: Some(name.name.clone()),
-                    };
-                }
-
-                ExprTy {
-                    ty: v.ty.clone(),
-                    is_secret: v.is_secret,
-                    copied_secret: v.is_secret,
-                    name_hint: Some(name.name.clone()),
+                    Ty::Error
                 }
+            };
+            ExprTy {
+                ty,
+                label,
+                copied_secret: false,
+                name_hint: None,
             }
-            None => {
+        }
+
+        Expr::StructLit { name, fields, span } => {
+            let Some(info) = cx.defs.structs.get(&name.name) else {
                 r.push(Diagnostic::error(
-                    "name-unknown",
-                    format!("Unknown name `{}`.", name.name),
+                    "type-unknown",
+                    format!("Unknown struct `{}`.", name.name),
                     name.span.clone(),
                 ));
-                ExprTy {
-                    ty: Ty::Unknown,
-                    is_secret: false,
-                    copied_secret: false,
-                    name_hint: Some(name.name.clone()),
+                for f in fields {
+                    let _ = type_of_expr(&f.expr, env, cx, r);
                 }
-            }
-        },
+                return ExprTy {
+                    ty: Ty::Error,
+                    label: Label::PUBLIC,
+                    copied_secret: false,
+                    name_hint: None,
+                };
+            };
 
-        Expr::Move { name, .. } => match env.get_mut(&name.name) {
-            Some(v) => {
-                if v.moved {
+            let mut seen: Vec<&str> = Vec::new();
+            let mut tainted = Label::PUBLIC;
+            for init in fields {
+                let vt = type_of_expr(&init.expr, env, cx, r);
+                check_secret_copy(
+                    &vt,
+                    &init.expr,
+                    &format!("{}: ", init.name.name),
+                    "",
+                    env,
+                    cx,
+                    r,
+                );
+
+                if seen.contains(&init.name.name.as_str()) {
                     r.push(Diagnostic::error(
-                        "use-after-move",
-                        format!("Use of `{}` after it was moved.", name.name),
-                        name.span.clone(),
+                        "struct-duplicate-field",
+                        format!("Field `{}` is initialized twice.", init.name.name),
+                        init.span.clone(),
                     ));
-                    return ExprTy {
-                        ty: Ty::Unknown,
-                        is_secret: v.is_secret,
-                        copied_secret: false,
-                        name_hint: Some(name.name.clone()),
-                    };
+                    continue;
                 }
-                v.moved = true;
+                seen.push(&init.name.name);
 
-                ExprTy {
-                    ty: v.ty.clone(),
-                    is_secret: v.is_secret,
-                    copied_secret: false,
-                    name_hint: Some(name.name.clone()),
+                let Some(fd) = info.fields.iter().find(|f| f.name == init.name.name) else {
+                    r.push(Diagnostic::error(
+                        "field-unknown",
+                        format!("Struct `{}` has no field `{}`.", name.name, init.name.name),
+                        init.name.span.clone(),
+                    ));
+                    continue;
+                };
+                if !cx.unify(&fd.ty, &vt.ty) {
+                    r.push(Diagnostic::error(
+                        mismatch_code(&fd.ty, &vt.ty, "type-mismatch"),
+                        format!(
+                            "Type mismatch: field `{}` is {}, got {}.",
+                            fd.name,
+                            fd.written,
+                            ty_name(&vt.ty)
+                        ),
+                        init.expr.span().clone(),
+                    ));
+                }
+                // Data in a field labeled lower does not lose its label: the value as a
+                // whole takes it instead.
+                if vt.label > fd.label {
+                    tainted = tainted.join(vt.label);
                 }
             }
-            None => {
+
+            let missing: Vec<&str> = info
+                .fields
+                .iter()
+                .filter(|f| !seen.contains(&f.name.as_str()))
+                .map(|f| f.name.as_str())
+                .collect();
+            if !missing.is_empty() {
                 r.push(Diagnostic::error(
-                    "name-unknown",
-                    format!("Unknown name `{}`.", name.name),
-                    name.span.clone(),
+                    "struct-missing-field",
+                    format!(
+                        "Missing field(s) in `{}` literal: {}.",
+                        name.name,
+                        missing.join(", ")
+                    ),
+                    span.clone(),
                 ));
-                ExprTy {
-                    ty: Ty::Unknown,
-                    is_secret: false,
-                    copied_secret: false,
-                    name_hint: Some(name.name.clone()),
-                }
             }
-        },
+
+            ExprTy {
+                ty: Ty::Struct(name.name.clone()),
+                label: tainted,
+                copied_secret: false,
+                name_hint: None,
+            }
+        }
 
         Expr::Call { callee, args, span } => {
             match callee.name.as_str() {
                 "log" => {
-                    require_effect(Effect::Io, span.clone(), current_effects, current_fn, r);
+                    require_effect(
+                        Effect::Io,
+                        span.clone(),
+                        cx.current_effects,
+                        cx.current_fn,
+                        r,
+                    );
                     if args.len() != 1 {
                         r.push(Diagnostic::error(
                             "call-arity",
@@ -528,17 +2840,38 @@ fn type_of_expr(
                         ));
                     }
                     for a in args {
-                        let _ = type_of_expr(a, env, current_effects, current_fn, fn_effects, r);
+                        let at = type_of_expr(a, env, cx, r);
+                        if at.ty != Ty::Str && at.ty != Ty::Error {
+                            r.push(Diagnostic::error(
+                                "arg-type-mismatch",
+                                format!("log expects a Str argument, got {}.", ty_name(&at.ty)),
+                                a.span().clone(),
+                            ));
+                        }
+                        // `log` writes to output, a public sink.
+                        if at.label.is_secret() {
+                            r.push(Diagnostic::error(
+                                "secret-leak-param",
+                                "Secret value passed to `log`, which writes it out; `declassify` it first.",
+                                a.span().clone(),
+                            ));
+                        }
                     }
                     return ExprTy {
                         ty: Ty::Unit,
-                        is_secret: false,
+                        label: Label::PUBLIC,
                         copied_secret: false,
                         name_hint: None,
                     };
                 }
                 "now" => {
-                    require_effect(Effect::Time, span.clone(), current_effects, current_fn, r);
+                    require_effect(
+                        Effect::Time,
+                        span.clone(),
+                        cx.current_effects,
+                        cx.current_fn,
+                        r,
+                    );
                     if !args.is_empty() {
                         r.push(Diagnostic::error(
                             "call-arity",
@@ -548,13 +2881,30 @@ fn type_of_expr(
                     }
                     return ExprTy {
                         ty: Ty::Int,
-                        is_secret: false,
+                        label: Label::PUBLIC,
                         copied_secret: false,
                         name_hint: None,
                     };
                 }
+                "declassify" => {
+                    require_effect(
+                        Effect::Declassify,
+                        span.clone(),
+                        cx.current_effects,
+                        cx.current_fn,
+                        r,
+                    );
+                    return type_of_declassify(callee, args, env, cx, r);
+                }
+                "wipe" => return type_of_wipe(callee, args, env, cx, r),
                 "rand" => {
-                    require_effect(Effect::Rand, span.clone(), current_effects, current_fn, r);
+                    require_effect(
+                        Effect::Rand,
+                        span.clone(),
+                        cx.current_effects,
+                        cx.current_fn,
+                        r,
+                    );
                     if !args.is_empty() {
                         r.push(Diagnostic::error(
                             "call-arity",
@@ -564,7 +2914,7 @@ fn type_of_expr(
                     }
                     return ExprTy {
                         ty: Ty::Int,
-                        is_secret: false,
+                        label: Label::PUBLIC,
                         copied_secret: false,
                         name_hint: None,
                     };
@@ -572,30 +2922,96 @@ fn type_of_expr(
                 _ => {}
             }
 
-            if !fn_effects.contains_key(&callee.name) {
+            if let Some((enum_name, v)) = cx.defs.variant(&callee.name) {
+                return type_of_variant_ctor(enum_name, v, callee, args, env, cx, r);
+            }
+
+            let Some(sig) = cx.fns.get(&callee.name) else {
                 r.push(Diagnostic::error(
                     "name-unknown",
                     format!("Unknown name `{}`.", callee.name),
                     callee.span.clone(),
                 ));
-            } else {
-                require_effects_for_call(
-                    &callee.name,
-                    span.clone(),
-                    current_effects,
-                    current_fn,
-                    fn_effects,
-                    r,
-                );
-            }
+                for a in args {
+                    let _ = type_of_expr(a, env, cx, r);
+                }
+                return ExprTy {
+                    ty: Ty::Error,
+                    label: Label::PUBLIC,
+                    copied_secret: false,
+                    name_hint: None,
+                };
+            };
 
-            for a in args {
-                let _ = type_of_expr(a, env, current_effects, current_fn, fn_effects, r);
+            require_effects_for_call(
+                &callee.name,
+                span.clone(),
+                cx.current_effects,
+                cx.current_fn,
+                &sig.effects,
+                r,
+            );
+            type_of_fn_call(sig, callee, args, env, cx, r)
+        }
+
+        Expr::Error { .. } => ExprTy {
+            ty: Ty::Error,
+            label: Label::PUBLIC,
+            copied_secret: false,
+            name_hint: None,
+        },
+
+        Expr::Unary { op, expr, .. } => {
+            let t = type_of_expr(expr, env, cx, r);
+            let want = match op {
+                UnOp::Neg => Ty::Int,
+                UnOp::Not => Ty::Bool,
+            };
+            if !cx.unify(&want, &t.ty) {
+                r.push(Diagnostic::error(
+                    "op-type-mismatch",
+                    format!(
+                        "Operator `{}` expects {}, got {}.",
+                        op.as_str(),
+                        ty_name(&want),
+                        ty_name(&t.ty)
+                    ),
+                    expr.span().clone(),
+                ));
+            }
+            // A derived value: secret if the operand is, but never a copy of it.
+            ExprTy {
+                ty: want,
+                label: t.label,
+                copied_secret: false,
+                name_hint: None,
             }
+        }
 
+        Expr::Binary { op, lhs, rhs, span } => {
+            let lt = type_of_expr(lhs, env, cx, r);
+            let rt = type_of_expr(rhs, env, cx, r);
+            let ty = type_of_binop(*op, &lt.ty, &rt.ty, span, cx, r);
+            if lt.label.is_secret() || rt.label.is_secret() {
+                match op {
+                    BinOp::Div | BinOp::Rem => check_ct(
+                        cx,
+                        &format!("`{}` on secret data takes variable time", op.as_str()),
+                        span,
+                        r,
+                    ),
+                    BinOp::And | BinOp::Or => check_ct(
+                        cx,
+                        &format!("`{}` short-circuits on secret data", op.as_str()),
+                        span,
+                        r,
+                    ),
+                    _ => {}
+                }
+            }
             ExprTy {
-                ty: Ty::Unknown,
-                is_secret: false,
+                ty,
+                label: lt.label.join(rt.label),
                 copied_secret: false,
                 name_hint: None,
             }
@@ -603,6 +3019,459 @@ fn type_of_expr(
     }
 }
 
+/// `declassify(x, "reason")`: the value of `x`, no longer secret. The reason must be a
+/// non-empty string literal so that the audit report can quote it.
+fn type_of_declassify(
+    callee: &Ident,
+    args: &[Expr],
+    env: &mut HashMap<String, VarInfo>,
+    cx: &FnCtx,
+    r: &mut DiagnosticReport,
+) -> ExprTy {
+    if args.len() != 2 {
+        r.push(Diagnostic::error(
+            "call-arity",
+            "declassify expects 2 arguments: a value and a reason.",
+            callee.span.clone(),
+        ));
+    }
+
+    let ty = match args.first() {
+        Some(a) => {
+            let ty = type_of_expr(a, env, cx, r).ty;
+            // The secret is handed over to become public: the variable is consumed.
+            if let Some((var, path)) = place_of(a) {
+                let path = path.into_iter().map(|f| f.name.clone()).collect();
+                if let Some(v) = env.get_mut(&var.name) {
+                    v.consume(path, &var.span);
+                }
+            }
+            ty
+        }
+        None => Ty::Error,
+    };
+
+    match args.get(1) {
+        Some(Expr::StrLit { value, .. }) if !value.trim().is_empty() => {}
+        Some(e) => r.push(Diagnostic::error(
+            "declassify-missing-reason",
+            "declassify needs a non-empty string literal explaining why the value may become public.",
+            e.span().clone(),
+        )),
+        None => {}
+    }
+    for a in args.iter().skip(2) {
+        let _ = type_of_expr(a, env, cx, r);
+    }
+
+    ExprTy {
+        ty,
+        label: Label::PUBLIC,
+        copied_secret: false,
+        name_hint: None,
+    }
+}
+
+/// `wipe(x)` destroys (zeroizes) a variable or field, consuming it like a move.
+fn type_of_wipe(
+    callee: &Ident,
+    args: &[Expr],
+    env: &mut HashMap<String, VarInfo>,
+    cx: &FnCtx,
+    r: &mut DiagnosticReport,
+) -> ExprTy {
+    if args.len() != 1 {
+        r.push(Diagnostic::error(
+            "call-arity",
+            "wipe expects exactly 1 argument.",
+            callee.span.clone(),
+        ));
+    }
+    for a in args {
+        let _ = type_of_expr(a, env, cx, r);
+        if let Some((var, path)) = place_of(a) {
+            let path = path.into_iter().map(|f| f.name.clone()).collect();
+            if let Some(v) = env.get_mut(&var.name) {
+                v.consume(path, &var.span);
+            }
+        }
+    }
+    ExprTy {
+        ty: Ty::Unit,
+        label: Label::PUBLIC,
+        copied_secret: false,
+        name_hint: None,
+    }
+}
+
+fn type_of_fn_call(
+    sig: &FnSig,
+    callee: &Ident,
+    args: &[Expr],
+    env: &mut HashMap<String, VarInfo>,
+    cx: &FnCtx,
+    r: &mut DiagnosticReport,
+) -> ExprTy {
+    if args.len() != sig.params.len() {
+        r.push(Diagnostic::error(
+            "call-arity",
+            format!(
+                "Function `{}` expects {} argument(s), got {}.",
+                callee.name,
+                sig.params.len(),
+                args.len()
+            ),
+            callee.span.clone(),
+        ));
+    }
+
+    // Each call instantiates the type parameters afresh; a parameter bound to a labeled
+    // argument gives everything typed by it at this call that label.
+    let inst: Vec<(String, Ty)> = sig
+        .generics
+        .iter()
+        .map(|g| (ty_name(g), cx.infer.borrow_mut().fresh()))
+        .collect();
+    let mut param_labels: Vec<(String, Label)> = Vec::new();
+    let mut passes_secret = false;
+
+    for (i, a) in args.iter().enumerate() {
+        let at = type_of_expr(a, env, cx, r);
+        passes_secret |= at.label.is_secret();
+        // Passing a secret hands it to the callee: the caller must give it up.
+        check_secret_copy(&at, a, "", "", env, cx, r);
+        let Some((pt, sec, written)) = sig.params.get(i) else {
+            continue;
+        };
+        let want = instantiate(pt, &inst);
+        if !cx.unify(&want, &at.ty) {
+            let want = cx.resolve(&want);
+            // A type parameter is reported as what this call instantiated it with.
+            let shown = match pt {
+                Ty::Param { .. } => ty_name(&want),
+                _ => written.clone(),
+            };
+            r.push(Diagnostic::error(
+                mismatch_code(&want, &at.ty, "arg-type-mismatch"),
+                format!(
+                    "Function `{}` expects {} for argument {}, got {}.",
+                    callee.name,
+                    shown,
+                    i + 1,
+                    ty_name(&at.ty)
+                ),
+                a.span().clone(),
+            ));
+        }
+        if let Ty::Param { name, .. } = pt {
+            if at.label > *sec {
+                param_labels.push((name.clone(), at.label));
+            }
+        } else if at.label.is_secret() && !sec.is_secret() {
+            r.push(Diagnostic::error(
+                "secret-leak-param",
+                format!(
+                    "Secret value passed to non-secret parameter {} of `{}`.",
+                    i + 1,
+                    callee.name
+                ),
+                a.span().clone(),
+            ));
+        } else {
+            let what = format!("parameter {} of `{}`", i + 1, callee.name);
+            check_label_flow(at.label, *sec, &what, a.span(), cx.defs, r);
+        }
+    }
+
+    for g in &sig.generics {
+        let Ty::Param { name, copy: true } = g else {
+            continue;
+        };
+        let ty = cx.resolve(&instantiate(g, &inst));
+        let secret = param_labels.iter().any(|(n, _)| n == name);
+        if secret || is_linear(&ty, false, cx.defs) {
+            r.push(Diagnostic::error(
+                "bound-not-satisfied",
+                format!(
+                    "`{}: Copy` in `{}` is not satisfied by {}{}, which must be moved.",
+                    name,
+                    callee.name,
+                    if secret { "secret " } else { "" },
+                    ty_name(&ty)
+                ),
+                callee.span.clone(),
+            ));
+        }
+    }
+
+    if passes_secret && !sig.ct {
+        check_ct(
+            cx,
+            &format!(
+                "it passes secret data to `{}`, which is not `ct`",
+                callee.name
+            ),
+            &callee.span,
+            r,
+        );
+    }
+
+    let ret_param_label = param_labels
+        .iter()
+        .filter(|(n, _)| matches!(&sig.ret, Ty::Param { name, .. } if name == n))
+        .map(|(_, l)| *l)
+        .max()
+        .unwrap_or_default();
+    ExprTy {
+        ty: cx.resolve(&instantiate(&sig.ret, &inst)),
+        label: sig.ret_label.join(ret_param_label),
+
+        copied_secret: false,
+        name_hint: None,
+    }
+}
+
+/// `Enum::Variant(args...)`: one argument per payload value. Secret data in a public
+/// payload makes the whole value secret, as for struct literals.
+fn type_of_variant_ctor(
+    enum_name: &str,
+    v: &VariantInfo,
+    callee: &Ident,
+    args: &[Expr],
+    env: &mut HashMap<String, VarInfo>,
+    cx: &FnCtx,
+    r: &mut DiagnosticReport,
+) -> ExprTy {
+    if args.len() != v.payload.len() {
+        r.push(Diagnostic::error(
+            "call-arity",
+            format!(
+                "Variant `{}` expects {} argument(s), got {}.",
+                callee.name,
+                v.payload.len(),
+                args.len()
+            ),
+            callee.span.clone(),
+        ));
+    }
+
+    let mut tainted = Label::PUBLIC;
+    for (i, a) in args.iter().enumerate() {
+        let at = type_of_expr(a, env, cx, r);
+        check_secret_copy(&at, a, "", "", env, cx, r);
+        let Some((pt, sec)) = v.payload.get(i) else {
+            continue;
+        };
+        if !cx.unify(pt, &at.ty) {
+            r.push(Diagnostic::error(
+                mismatch_code(pt, &at.ty, "arg-type-mismatch"),
+                format!(
+                    "Variant `{}` expects {} for argument {}, got {}.",
+                    callee.name,
+                    ty_name(pt),
+                    i + 1,
+                    ty_name(&at.ty)
+                ),
+                a.span().clone(),
+            ));
+        }
+        if at.label > *sec {
+            tainted = tainted.join(at.label);
+        }
+    }
+
+    ExprTy {
+        ty: Ty::Enum(enum_name.to_string()),
+        label: tainted,
+        copied_secret: false,
+        name_hint: None,
+    }
+}
+
+/// Length of an indexed/sliced value; `type-mismatch` unless it is `Bytes[N]`.
+fn bytes_len(ty: &Ty, base: &Expr, r: &mut DiagnosticReport) -> Option<u64> {
+    match ty {
+        Ty::Bytes(n) => Some(*n),
+        Ty::Error => None,
+        Ty::Var(_) => {
+            r.push(Diagnostic::error(
+                "type-ambiguous",
+                "Cannot infer the type of the indexed value; add a type annotation.",
+                base.span().clone(),
+            ));
+            None
+        }
+        other => {
+            r.push(Diagnostic::error(
+                "type-mismatch",
+                format!(
+                    "Only Bytes[N] values can be indexed, got {}.",
+                    ty_name(other)
+                ),
+                base.span().clone(),
+            ));
+            None
+        }
+    }
+}
+
+/// An index (or slice bound, which may equal `len`). Secret indices are rejected outright:
+/// the memory access pattern would leak them through the cache.
+fn check_array_bound(
+    e: &Expr,
+    len: Option<u64>,
+    is_slice_bound: bool,
+    env: &mut HashMap<String, VarInfo>,
+    cx: &FnCtx,
+    r: &mut DiagnosticReport,
+) -> ExprTy {
+    let it = type_of_expr(e, env, cx, r);
+    if !cx.unify(&Ty::Int, &it.ty) {
+        r.push(Diagnostic::error(
+            "type-mismatch",
+            format!("Array index must be Int, got {}.", ty_name(&it.ty)),
+            e.span().clone(),
+        ));
+    }
+    if it.label.is_secret() {
+        if cx.current_fn.ct {
+            check_ct(
+                cx,
+                "indexing with a secret value makes the memory access pattern depend on it",
+                e.span(),
+                r,
+            );
+        } else {
+            r.push(Diagnostic::error(
+                "secret-index",
+                "Indexing with a secret index is forbidden (cache-timing side channel).",
+                e.span().clone(),
+            ));
+        }
+        return it;
+    }
+
+    let value = const_int(e, cx.defs);
+    if is_slice_bound && value.is_none() && cx.resolve(&it.ty) == Ty::Int {
+        r.push(Diagnostic::error(
+            "slice-bound-not-constant",
+            "Slice bounds must be compile-time constants, so the slice length is static.",
+            e.span().clone(),
+        ));
+    }
+    if let (Some(v), Some(n)) = (value, len) {
+        let max = if is_slice_bound {
+            n
+        } else {
+            n.saturating_sub(1)
+        };
+        if v < 0 || v as u64 > max || (!is_slice_bound && n == 0) {
+            r.push(Diagnostic::error(
+                "index-out-of-bounds",
+                format!("Index {} is out of bounds for Bytes[{}].", v, n),
+                e.span().clone(),
+            ));
+        }
+    }
+    it
+}
+
+/// One element of a Bytes literal: an Int in 0..=255. Returns its label.
+fn check_byte_elem(
+    e: &Expr,
+    env: &mut HashMap<String, VarInfo>,
+    cx: &FnCtx,
+    r: &mut DiagnosticReport,
+) -> Label {
+    let et = type_of_expr(e, env, cx, r);
+    check_secret_copy(&et, e, "", "", env, cx, r);
+    if !cx.unify(&Ty::Int, &et.ty) {
+        r.push(Diagnostic::error(
+            "type-mismatch",
+            format!("Bytes elements must be Int, got {}.", ty_name(&et.ty)),
+            e.span().clone(),
+        ));
+    }
+    if let Some(v) = const_int(e, cx.defs) {
+        if !(0..=255).contains(&v) {
+            r.push(Diagnostic::error(
+                "byte-out-of-range",
+                format!("Byte value {} is out of range 0..=255.", v),
+                e.span().clone(),
+            ));
+        }
+    }
+    et.label
+}
+
+/// Operand rules: arithmetic and ordering on Int, `&&`/`||` on Bool,
+/// `==`/`!=` on two operands of the same type (not a type parameter). Returns the result type.
+fn type_of_binop(
+    op: BinOp,
+    lt: &Ty,
+    rt: &Ty,
+    span: &Span,
+    cx: &FnCtx,
+    r: &mut DiagnosticReport,
+) -> Ty {
+    let (operand, result) = if op.is_arithmetic() {
+        (Some(Ty::Int), Ty::Int)
+    } else if op.is_logical() {
+        (Some(Ty::Bool), Ty::Bool)
+    } else if matches!(op, BinOp::Eq | BinOp::Ne) {
+        (None, Ty::Bool)
+    } else {
+        (Some(Ty::Int), Ty::Bool)
+    };
+
+    if *lt == Ty::Error || *rt == Ty::Error {
+        return result;
+    }
+
+    if operand.is_none() {
+        if let Some(t) = [lt, rt].into_iter().find_map(|t| match cx.resolve(t) {
+            Ty::Param { name, .. } => Some(name),
+            _ => None,
+        }) {
+            r.push(Diagnostic::error(
+                "op-type-mismatch",
+                format!(
+                    "Equality is not defined on generic type parameters; `{}` cannot compare values of type `{}`.",
+                    op.as_str(),
+                    t
+                ),
+                span.clone(),
+            ));
+            return result;
+        }
+    }
+
+    let ok = match &operand {
+        Some(want) => cx.unify(want, lt) & cx.unify(want, rt),
+        None => {
+            cx.unify(lt, rt) && !matches!(cx.resolve(lt), Ty::Unit | Ty::Struct(_) | Ty::Enum(_))
+        }
+    };
+    if !ok {
+        let expected = match &operand {
+            Some(want) => format!("{} operands", ty_name(want)),
+            None => "two operands of the same type".to_string(),
+        };
+        r.push(Diagnostic::error(
+            "op-type-mismatch",
+            format!(
+                "Operator `{}` expects {}, got {} and {}.",
+                op.as_str(),
+                expected,
+                ty_name(lt),
+                ty_name(rt)
+            ),
+            span.clone(),
+        ));
+    }
+    result
+}
+
 fn typecheck_protocols(protocols: &[candy_ast::ProtocolDecl], r: &mut DiagnosticReport) {
     use std::collections::{HashMap, HashSet};
 
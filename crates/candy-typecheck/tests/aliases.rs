@@ -0,0 +1,134 @@
+use candy_parser::parse_program;
+use candy_typecheck::typecheck;
+
+fn diags(src: &str) -> Vec<(String, String)> {
+    let p = parse_program(src).expect("parse ok");
+    match typecheck(&p) {
+        Ok(()) => vec![],
+        Err(r) => r
+            .diagnostics
+            .into_iter()
+            .map(|d| (d.code, d.message))
+            .collect(),
+    }
+}
+
+fn codes(src: &str) -> Vec<String> {
+    diags(src).into_iter().map(|(c, _)| c).collect()
+}
+
+#[test]
+fn secret_alias_keeps_linear_rules() {
+    let src = r#"
+type Key = secret Int;
+fn main() -> Unit {
+  let k: Key = 1;
+  let j: Key = k;
+  wipe(j);
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["secret-copy"]);
+}
+
+#[test]
+fn secret_alias_cannot_be_branched_on() {
+    let src = r#"
+type Flag = secret Bool;
+fn main() -> Unit {
+  let f: Flag = true;
+  if (f) { }
+  wipe(f);
+
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["secret-branch"]);
+}
+
+#[test]
+fn aliases_chain_and_work_in_signatures() {
+    let src = r#"
+type Nonce = Bytes[12];
+type SecretNonce = secret Nonce;
+struct Sealed { n: Nonce }
+fn seal(n: SecretNonce) -> Sealed { wipe(n); return Sealed { n: [0; 12] }; }
+fn main() -> Unit {
+  let n: SecretNonce = [0; 12];
+  let s: Sealed = seal(move(n));
+  return;
+}
+"#;
+    assert!(codes(src).is_empty(), "{:?}", codes(src));
+}
+
+#[test]
+fn diagnostics_print_the_alias_name() {
+    let src = r#"
+type Key = secret Int;
+fn take(k: Key) -> Unit { wipe(k); return; }
+fn main() -> Unit {
+  let k: Key = true;
+  take(false);
+  wipe(k);
+  return;
+
+}
+"#;
+    let d = diags(src);
+    assert_eq!(d.len(), 2, "{d:?}");
+    assert_eq!(d[0].1, "Type mismatch: expected Key, got Bool.");
+    assert_eq!(
+        d[1].1,
+        "Function `take` expects Key for argument 1, got Bool."
+    );
+}
+
+#[test]
+fn public_alias_is_not_secret() {
+    let src = r#"
+type PubKey = Int;
+fn derive(k: secret Int) -> PubKey { return k; }
+fn main() -> Unit { return; }
+"#;
+    let p = parse_program(src).expect("parse ok");
+    let err = typecheck(&p).expect_err("should fail");
+    assert_eq!(err.diagnostics[0].code, "secret-leak-return");
+    assert_eq!(
+        err.diagnostics[0].fix.as_ref().unwrap().with,
+        "-> secret PubKey"
+    );
+}
+
+#[test]
+fn alias_cycle_is_reported_once_per_alias() {
+    let src = r#"
+type A = B;
+type B = secret A;
+type C = A;
+fn main() -> Unit { let x: C = 1; return; }
+"#;
+    let d = diags(src);
+    let codes: Vec<&str> = d.iter().map(|(c, _)| c.as_str()).collect();
+    assert_eq!(codes, vec!["alias-cycle", "alias-cycle"]);
+    assert!(d[0].1.contains("A -> B -> A"), "{}", d[0].1);
+}
+
+#[test]
+fn alias_to_unknown_type_is_reported_at_the_alias() {
+    let src = r#"
+type K = Nope;
+fn main() -> Unit { let x: K = 1; return; }
+"#;
+    assert_eq!(codes(src), vec!["type-unknown"]);
+}
+
+#[test]
+fn alias_name_clash_is_reported() {
+    let src = r#"
+struct Key { a: Int }
+type Key = Int;
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(codes(src), vec!["alias-duplicate"]);
+}
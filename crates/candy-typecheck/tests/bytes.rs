@@ -0,0 +1,126 @@
+use candy_parser::parse_program;
+use candy_typecheck::typecheck;
+
+fn codes(src: &str) -> Vec<String> {
+    let p = parse_program(src).expect("parse ok");
+    match typecheck(&p) {
+        Ok(()) => vec![],
+        Err(r) => r.diagnostics.into_iter().map(|d| d.code).collect(),
+    }
+}
+
+fn in_main(body: &str) -> String {
+    format!("fn main() -> Unit {{\n{body}\n  return;\n}}\n")
+}
+
+#[test]
+fn literals_indexing_and_slicing() {
+    let src = in_main(
+        r#"
+  let nonce: Bytes[4] = [0x00, 0x01, 0x02, 0xff];
+  let zero: Bytes[32] = [0; 32];
+  let first: Int = nonce[0];
+  let tail: Bytes[2] = nonce[2..4];
+  for i in 0..4 {
+    let b: Int = nonce[i];
+  }
+"#,
+    );
+    assert!(codes(&src).is_empty(), "{:?}", codes(&src));
+}
+
+#[test]
+fn length_mismatch_is_reported() {
+    let src = in_main(
+        r#"
+  let k: Bytes[32] = [0; 16];
+  let h: Bytes[4] = k[0..2];
+"#,
+    );
+    assert_eq!(codes(&src), vec!["length-mismatch", "length-mismatch"]);
+}
+
+#[test]
+fn secret_index_is_rejected() {
+    let src = in_main(
+        r#"
+  let table: Bytes[4] = [1, 2, 3, 4];
+  let s: secret Int = 2;
+  let v: Int = table[s + 0];
+  wipe(s);
+  wipe(v);
+"#,
+    );
+    assert_eq!(codes(&src), vec!["secret-index"]);
+}
+
+#[test]
+fn reading_a_secret_array_yields_secret_bytes() {
+    let src = in_main(
+        r#"
+  let k: secret Bytes[2] = [7, 8];
+  let b: Int = k[0];
+  if (b == 7) { }
+  wipe(k);
+  wipe(b);
+"#,
+    );
+    assert_eq!(codes(&src), vec!["secret-branch"]);
+}
+
+#[test]
+fn slicing_secret_array_copies_it() {
+    let src = in_main(
+        r#"
+  let k: secret Bytes[4] = [1, 2, 3, 4];
+  let half: secret Bytes[2] = k[0..2];
+  let moved: secret Bytes[2] = move(k)[2..4];
+  wipe(half);
+  wipe(moved);
+"#,
+    );
+    assert_eq!(codes(&src), vec!["secret-copy"]);
+}
+
+#[test]
+fn bounds_and_constant_checks() {
+    let src = in_main(
+        r#"
+  let n: Int = 2;
+  let a: Bytes[4] = [1, 2, 3, 256];
+  let b: Int = a[4];
+  let c: Bytes[2] = a[0..n];
+  let d: Bytes[0] = a[3..1];
+"#,
+    );
+    assert_eq!(
+        codes(&src),
+        vec![
+            "byte-out-of-range",
+            "index-out-of-bounds",
+            "slice-bound-not-constant",
+            "index-out-of-bounds"
+        ]
+    );
+}
+
+#[test]
+fn non_constant_length_is_reported() {
+    let src = r#"
+fn f(k: Bytes[1 + 1]) -> Unit { return; }
+fn g(m: Bytes[true]) -> Unit { return; }
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(codes(src), vec!["length-not-constant"]);
+}
+
+#[test]
+fn indexing_non_array_is_type_error() {
+    let src = in_main(
+        r#"
+  let x: Int = 5;
+  let y: Int = x[0];
+"#,
+    );
+    assert_eq!(codes(&src), vec!["type-mismatch"]);
+}
@@ -0,0 +1,181 @@
+use candy_parser::parse_program;
+use candy_typecheck::typecheck;
+
+fn codes(src: &str) -> Vec<String> {
+    let p = parse_program(src).expect("parse ok");
+    match typecheck(&p) {
+        Ok(()) => vec![],
+        Err(r) => r.diagnostics.into_iter().map(|d| d.code).collect(),
+    }
+}
+
+#[test]
+fn well_typed_call_passes() {
+    let src = r#"
+fn add(a: Int, b: Int) -> Int { return a + b; }
+fn main() -> Unit {
+  let x: Int = add(1, 2);
+  return;
+}
+"#;
+    assert!(codes(src).is_empty(), "{:?}", codes(src));
+}
+
+#[test]
+fn call_gets_declared_return_type() {
+    let src = r#"
+fn f() -> Int { return 1; }
+fn main() -> Unit {
+  let x: Bool = f();
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["type-mismatch"]);
+}
+
+#[test]
+fn wrong_argument_count_is_rejected() {
+    let src = r#"
+fn add(a: Int, b: Int) -> Int { return a + b; }
+fn main() -> Unit {
+  let x: Int = add(1);
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["call-arity"]);
+}
+
+#[test]
+fn each_argument_is_checked() {
+    let src = r#"
+fn pick(a: Int, b: Bool, c: Str) -> Int { return a; }
+fn main() -> Unit {
+  let x: Int = pick(true, false, 3);
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["arg-type-mismatch", "arg-type-mismatch"]);
+}
+
+#[test]
+fn byte_array_argument_length_is_checked() {
+    let src = r#"
+fn take(k: Bytes[4]) -> Unit { return; }
+fn main() -> Unit {
+  let b: Bytes[2] = [0; 2];
+  take(b);
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["length-mismatch"]);
+}
+
+#[test]
+fn struct_return_type_flows_into_field_access() {
+    let src = r#"
+struct P { a: Int }
+fn make() -> P { return P { a: 1 }; }
+fn main() -> Unit {
+  let p: P = make();
+  let b: Bool = p.a;
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["type-mismatch"]);
+}
+
+#[test]
+fn secret_argument_must_be_moved() {
+    let src = r#"
+fn seal(k: secret Int) -> Unit { wipe(k); return; }
+fn main() -> Unit {
+  let k: secret Int = 1;
+  seal(k);
+  return;
+
+}
+"#;
+    let p = parse_program(src).expect("parse ok");
+    let err = typecheck(&p).expect_err("should fail");
+    assert_eq!(err.diagnostics.len(), 1);
+    let d = &err.diagnostics[0];
+    assert_eq!(d.code, "secret-copy");
+    let fix = d.fix.as_ref().expect("fix");
+    assert_eq!(fix.replace, "k");
+    assert_eq!(fix.with, "move(k)");
+}
+
+#[test]
+fn moved_secret_argument_is_consumed() {
+    let src = r#"
+fn seal(k: secret Int) -> Unit { wipe(k); return; }
+fn main() -> Unit {
+  let k: secret Int = 1;
+  seal(move(k));
+  let j: secret Int = move(k);
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["use-after-move"]);
+}
+
+#[test]
+fn secret_bound_to_public_parameter_leaks() {
+    let src = r#"
+fn show(x: Int) -> Unit { return; }
+fn main() -> Unit {
+  let k: secret Int = 1;
+  show(move(k) + 1);
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["secret-leak-param"]);
+}
+
+#[test]
+fn returning_secret_from_public_function_leaks() {
+    let src = r#"
+fn derive(k: secret Int) -> Int { return k + 1; }
+fn main() -> Unit { return; }
+"#;
+    let p = parse_program(src).expect("parse ok");
+    let err = typecheck(&p).expect_err("should fail");
+    let d = &err.diagnostics[0];
+    assert_eq!(d.code, "secret-leak-return");
+    let fix = d.fix.as_ref().expect("fix");
+    assert_eq!(fix.with, "-> secret Int");
+}
+
+#[test]
+fn secret_return_type_makes_call_result_secret() {
+    let src = r#"
+fn derive() -> secret Int { return 7; }
+fn main() -> Unit {
+  let k: Int = derive();
+  if (k == 0) { }
+  wipe(k);
+  return;
+
+}
+"#;
+    assert_eq!(codes(src), vec!["secret-branch"]);
+}
+
+#[test]
+fn duplicate_parameter_is_rejected() {
+    let src = r#"
+fn f(a: secret Int, a: Int) -> Unit { return; }
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(codes(src), vec!["param-duplicate", "secret-not-consumed"]);
+}
+
+#[test]
+fn duplicate_function_is_rejected() {
+    let src = r#"
+fn f() -> Int { return 1; }
+fn f(a: Bool) -> Bool { return a; }
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(codes(src), vec!["fn-duplicate"]);
+}
@@ -0,0 +1,123 @@
+use candy_parser::parse_program;
+use candy_typecheck::typecheck;
+
+fn diags(src: &str) -> Vec<(String, String)> {
+    let p = parse_program(src).expect("parse ok");
+    match typecheck(&p) {
+        Ok(()) => vec![],
+        Err(r) => r
+            .diagnostics
+            .into_iter()
+            .map(|d| (d.code, d.message))
+            .collect(),
+    }
+}
+
+fn codes(src: &str) -> Vec<String> {
+    diags(src).into_iter().map(|(c, _)| c).collect()
+}
+
+#[test]
+fn consts_work_as_loop_bounds_and_array_lengths() {
+    let src = r#"
+const ROUNDS: Int = 10;
+const KEY_LEN: Int = HALF * 2;
+const HALF: Int = 16;
+fn main() -> Unit {
+  let k: Bytes[KEY_LEN] = [0; 32];
+  let n: Bytes[32] = k;
+  for i in 0..ROUNDS { }
+  let r: Int = ROUNDS + 1;
+  return;
+}
+"#;
+    assert_eq!(codes(src), Vec::<String>::new());
+}
+
+#[test]
+fn const_length_is_folded_for_mismatches() {
+    let src = r#"
+const N: Int = 4;
+fn main() -> Unit {
+  let a: Bytes[N + 1] = [0; 4];
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["length-mismatch"]);
+}
+
+#[test]
+fn const_calling_a_function_is_rejected() {
+    let src = r#"
+const T: Int = now();
+fn main() -> Unit { return; }
+"#;
+    let d = diags(src);
+    assert_eq!(d.len(), 1);
+    assert_eq!(d[0].0, "const-not-constant");
+    assert!(d[0].1.contains("`now`"), "{}", d[0].1);
+}
+
+#[test]
+fn const_reading_a_variable_is_rejected() {
+    let src = r#"
+const T: Int = x + 1;
+fn main() -> Unit { return; }
+"#;
+    let d = diags(src);
+    assert_eq!(d.len(), 1);
+    assert_eq!(d[0].0, "const-not-constant");
+    assert!(d[0].1.contains("`x`"), "{}", d[0].1);
+}
+
+#[test]
+fn self_referential_consts_are_reported_once() {
+    let src = r#"
+const A: Int = B + 1;
+const B: Int = A;
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(codes(src), vec!["const-not-constant"]);
+}
+
+#[test]
+fn overflow_and_division_by_zero_are_not_constant() {
+    let src = r#"
+const BIG: Int = 9223372036854775807 + 1;
+const Z: Int = 1 / 0;
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(codes(src), vec!["const-not-constant", "const-not-constant"]);
+}
+
+#[test]
+fn const_value_must_match_declared_type() {
+    let src = r#"
+const F: Bool = 1 + 2;
+const G: Int = 1 == 2;
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(codes(src), vec!["type-mismatch", "type-mismatch"]);
+}
+
+#[test]
+fn duplicate_consts_are_reported() {
+    let src = r#"
+const N: Int = 1;
+const N: Int = 2;
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(codes(src), vec!["const-duplicate"]);
+}
+
+#[test]
+fn secret_const_follows_secret_rules() {
+    let src = r#"
+const K: secret Int = 7;
+fn main() -> Unit {
+  if (K == 7) { return; }
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["secret-branch"]);
+}
@@ -0,0 +1,148 @@
+use candy_diagnostics::Diagnostic;
+use candy_parser::parse_program;
+use candy_typecheck::typecheck;
+
+fn diags(src: &str) -> Vec<Diagnostic> {
+    let p = parse_program(src).expect("parse ok");
+    match typecheck(&p) {
+        Ok(()) => vec![],
+        Err(r) => r.diagnostics,
+    }
+}
+
+fn codes(src: &str) -> Vec<String> {
+    diags(src).into_iter().map(|d| d.code).collect()
+}
+
+#[test]
+fn unconsumed_secret_is_reported_at_the_binding() {
+    let src = r#"
+fn main() -> Unit {
+  let k: secret Int = 1;
+  return;
+}
+"#;
+    let d = diags(src);
+    assert_eq!(d.len(), 1);
+    assert_eq!(d[0].code, "secret-not-consumed");
+    assert_eq!(d[0].span.start_line, 3);
+    assert_eq!(d[0].span.start_col, 7);
+    let fix = d[0].fix.as_ref().expect("fix");
+    assert_eq!(fix.replace, "return");
+    assert_eq!(fix.with, "wipe(k);\nreturn");
+}
+
+#[test]
+fn wipe_move_return_and_call_consume() {
+    let src = r#"
+fn seal(k: secret Int) -> Unit { wipe(k); return; }
+fn pass(k: secret Int) -> secret Int { return k; }
+fn main() -> Unit {
+  let a: secret Int = 1;
+  let b: secret Int = pass(move(a));
+  seal(move(b));
+  return;
+}
+"#;
+    assert_eq!(codes(src), Vec::<String>::new());
+}
+
+#[test]
+fn use_after_wipe_is_use_after_move() {
+    let src = r#"
+fn main() -> Unit {
+  let k: secret Int = 1;
+  wipe(k);
+  wipe(k);
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["use-after-move"]);
+}
+
+#[test]
+fn unconsumed_parameter_at_end_of_body() {
+    let src = r#"
+fn drop_it(k: secret Int) -> Unit { }
+fn main() -> Unit { return; }
+"#;
+    let d = diags(src);
+    assert_eq!(d.len(), 1);
+    assert_eq!(d[0].code, "secret-not-consumed");
+    assert_eq!(d[0].fix.as_ref().unwrap().with, "wipe(k);\n}");
+}
+
+#[test]
+fn consumed_on_one_branch_only_is_reported() {
+    let src = r#"
+fn main() -> Unit {
+  let c: Bool = true;
+  let k: secret Int = 1;
+  if (c) { wipe(k); }
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["secret-not-consumed"]);
+}
+
+#[test]
+fn each_binding_is_reported_once() {
+    let src = r#"
+fn f(c: Bool) -> Int {
+  let k: secret Int = 1;
+  if (c) { return 1; }
+  return 2;
+}
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(codes(src), vec!["secret-not-consumed"]);
+}
+
+#[test]
+fn loop_local_secret_must_be_consumed_in_the_body() {
+    let src = r#"
+fn main() -> Unit {
+  for i in 0..2 {
+    let k: secret Int = i;
+  }
+  return;
+}
+"#;
+    let d = diags(src);
+    assert_eq!(d.len(), 1);
+    assert_eq!(d[0].code, "secret-not-consumed");
+    assert_eq!(d[0].fix.as_ref().unwrap().replace, "}");
+}
+
+#[test]
+fn struct_is_consumed_once_its_secret_fields_are() {
+    let src = r#"
+struct KeyPair { pub_key: Int, priv_key: secret Int }
+fn main() -> Unit {
+  let kp = KeyPair { pub_key: 1, priv_key: 2 };
+  wipe(kp.priv_key);
+  let p: Int = kp.pub_key;
+  return;
+}
+"#;
+    assert_eq!(codes(src), Vec::<String>::new());
+}
+
+#[test]
+fn declassify_consumes_its_argument() {
+    let src = r#"
+fn main() -> Unit effects(declassify) {
+  let k: secret Int = 1;
+  let p: Int = declassify(k, "public by design");
+  k;
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["use-after-move"]);
+}
+
+#[test]
+fn wipe_takes_one_argument() {
+    let src = "fn main() -> Unit { wipe(); return; }";
+    assert_eq!(codes(src), vec!["call-arity"]);
+}
@@ -0,0 +1,134 @@
+use candy_diagnostics::Diagnostic;
+use candy_parser::parse_program;
+use candy_typecheck::typecheck;
+
+fn diags(src: &str) -> Vec<Diagnostic> {
+    let p = parse_program(src).expect("parse ok");
+    match typecheck(&p) {
+        Ok(()) => vec![],
+        Err(r) => r.diagnostics,
+    }
+}
+
+fn codes(src: &str) -> Vec<String> {
+    diags(src).into_iter().map(|d| d.code).collect()
+}
+
+#[test]
+fn constant_time_body_passes() {
+    let src = r#"
+fn eq(a: secret Int, b: secret Int) -> secret Bool ct {
+  return move(a) == move(b);
+}
+fn mix(a: secret Int, b: secret Int) -> secret Int ct {
+  let d: secret Bool = eq(move(a), 0);
+  wipe(d);
+  return move(b) * 3 + 1;
+}
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(codes(src), Vec::<String>::new());
+}
+
+#[test]
+fn division_on_secret_is_a_violation() {
+    let src = r#"
+fn half(a: secret Int) -> secret Int ct {
+  return move(a) / 2;
+}
+fn main() -> Unit { return; }
+"#;
+    let d = diags(src);
+    assert_eq!(d.len(), 1);
+    assert_eq!(d[0].code, "ct-violation");
+    assert_eq!(
+        d[0].message,
+        "`half` is `ct`, but `/` on secret data takes variable time."
+    );
+}
+
+#[test]
+fn short_circuit_on_secret_is_a_violation() {
+    let src = r#"
+fn both(a: secret Bool, b: secret Bool) -> secret Bool ct {
+  return move(a) && move(b);
+}
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(codes(src), vec!["ct-violation"]);
+}
+
+#[test]
+fn secret_argument_to_non_ct_function_is_a_violation() {
+    let src = r#"
+fn slow(x: secret Int) -> secret Int { return x; }
+fn fast(x: secret Int) -> secret Int ct {
+  let n: Int = 4;
+  let p: Int = public_only(n);
+  return slow(move(x));
+}
+fn public_only(n: Int) -> Int { return n; }
+fn main() -> Unit { return; }
+"#;
+    let d = diags(src);
+    assert_eq!(d.len(), 1);
+    assert_eq!(d[0].code, "ct-violation");
+    assert!(
+        d[0].message.contains("`slow`, which is not `ct`"),
+        "{}",
+        d[0].message
+    );
+}
+
+#[test]
+fn secret_index_in_ct_function_is_a_violation() {
+    let src = r#"
+fn lookup(i: secret Int) -> Int ct {
+  let table: Bytes[4] = [1, 2, 3, 4];
+  let v: Int = table[i + 0];
+  wipe(i);
+  wipe(v);
+  return 0;
+}
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(codes(src), vec!["ct-violation"]);
+}
+
+#[test]
+fn secret_dependent_return_is_a_violation() {
+    let src = r#"
+fn check(a: secret Bool) -> Int ct {
+  if (a) { wipe(a); return 1; }
+  wipe(a);
+  return 0;
+}
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(codes(src), vec!["secret-branch", "ct-violation"]);
+}
+
+#[test]
+fn rules_apply_only_to_ct_functions() {
+    let src = r#"
+fn half(a: secret Int) -> secret Int {
+  return move(a) / 2;
+}
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(codes(src), Vec::<String>::new());
+}
+
+#[test]
+fn effect_fix_keeps_the_marker() {
+    let src = r#"
+fn f() -> Unit ct { log("x"); return; }
+fn main() -> Unit { return; }
+"#;
+    let d = diags(src);
+    assert_eq!(d[0].code, "undeclared-effect");
+    assert_eq!(
+        d[0].fix.as_ref().unwrap().with,
+        "fn f(...) -> Unit ct effects(io) {"
+    );
+}
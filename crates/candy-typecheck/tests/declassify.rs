@@ -0,0 +1,109 @@
+use candy_parser::parse_program;
+use candy_typecheck::{declassifications, typecheck};
+
+fn codes(src: &str) -> Vec<String> {
+    let p = parse_program(src).expect("parse ok");
+    match typecheck(&p) {
+        Ok(()) => vec![],
+        Err(r) => r.diagnostics.into_iter().map(|d| d.code).collect(),
+    }
+}
+
+#[test]
+fn declassified_value_is_public() {
+    let src = r#"
+fn main() -> Unit effects(declassify) {
+  let tag: secret Bool = true;
+  let ok: Bool = declassify(tag, "MAC check result is public");
+  if (ok) { return; }
+  return;
+}
+"#;
+    assert!(codes(src).is_empty(), "{:?}", codes(src));
+}
+
+#[test]
+fn declassify_requires_effect() {
+    let src = r#"
+fn main() -> Unit {
+  let k: secret Int = 1;
+  let p: Int = declassify(k, "public key");
+  return;
+}
+"#;
+    let p = parse_program(src).expect("parse ok");
+    let err = typecheck(&p).expect_err("should fail");
+    assert_eq!(err.diagnostics.len(), 1);
+    assert_eq!(err.diagnostics[0].code, "undeclared-effect");
+    assert_eq!(
+        err.diagnostics[0].fix.as_ref().unwrap().with,
+        "fn main(...) -> Unit effects(declassify) {"
+    );
+}
+
+#[test]
+fn reason_must_be_non_empty_literal() {
+    let src = r#"
+fn main() -> Unit effects(declassify) {
+  let k: secret Int = 1;
+  let j: secret Int = 2;
+  let m: secret Int = 3;
+  let why: Str = "x";
+  let a: Int = declassify(k, "  ");
+  let b: Int = declassify(j, why);
+  let c: Int = declassify(m);
+
+  return;
+}
+"#;
+    assert_eq!(
+        codes(src),
+        vec![
+            "declassify-missing-reason",
+            "declassify-missing-reason",
+            "call-arity"
+        ]
+    );
+}
+
+#[test]
+fn callers_inherit_the_effect() {
+    let src = r#"
+fn reveal(k: secret Int) -> Int effects(declassify) { return declassify(k, "demo"); }
+fn main() -> Unit {
+  let k: secret Int = 1;
+  let p: Int = reveal(move(k));
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["effect-leak"]);
+}
+
+#[test]
+fn sites_are_listed_with_reasons() {
+    let src = r#"
+fn reveal(k: secret Int) -> Int effects(declassify) {
+  if (true) { return declassify(k + 1, "derived public key"); }
+  return 0;
+}
+fn main() -> Unit effects(declassify) {
+  let t: secret Bool = true;
+  let ok: Bool = declassify(t, "MAC verification result");
+  return;
+}
+"#;
+    let p = parse_program(src).expect("parse ok");
+    let sites = declassifications(&p);
+    let got: Vec<(&str, &str)> = sites
+        .iter()
+        .map(|d| (d.function.as_str(), d.reason.as_str()))
+        .collect();
+    assert_eq!(
+        got,
+        vec![
+            ("reveal", "derived public key"),
+            ("main", "MAC verification result")
+        ]
+    );
+    assert_eq!(sites[0].span.start_line, 3);
+}
@@ -0,0 +1,148 @@
+use candy_parser::parse_program;
+use candy_typecheck::typecheck;
+
+fn diags(src: &str) -> Vec<(String, String)> {
+    let p = parse_program(src).expect("parse ok");
+    match typecheck(&p) {
+        Ok(()) => vec![],
+        Err(r) => r
+            .diagnostics
+            .into_iter()
+            .map(|d| (d.code, d.message))
+            .collect(),
+    }
+}
+
+fn codes(src: &str) -> Vec<String> {
+    diags(src).into_iter().map(|(c, _)| c).collect()
+}
+
+#[test]
+fn branches_are_checked_independently() {
+    let src = r#"
+fn main() -> Unit {
+  let c: Bool = true;
+  let k: secret Int = 1;
+  if (c) {
+    wipe(k);
+  } else {
+    wipe(k);
+  }
+  return;
+}
+"#;
+    assert_eq!(codes(src), Vec::<String>::new());
+}
+
+#[test]
+fn move_on_one_branch_is_maybe_move() {
+    let src = r#"
+fn main() -> Unit {
+  let c: Bool = true;
+  let k: secret Int = 1;
+  if (c) {
+    wipe(k);
+  }
+  wipe(k);
+  return;
+}
+"#;
+    let d = diags(src);
+    assert_eq!(d.len(), 1);
+    assert_eq!(d[0].0, "use-after-maybe-move");
+    assert_eq!(d[0].1, "Use of `k` after it was moved on some paths.");
+}
+
+#[test]
+fn move_on_both_branches_is_use_after_move() {
+    let src = r#"
+fn main() -> Unit {
+  let c: Bool = true;
+  let k: secret Int = 1;
+  if (c) {
+    wipe(k);
+  } else {
+    wipe(k);
+  }
+  k;
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["use-after-move"]);
+}
+
+#[test]
+fn returning_branch_does_not_reach_the_join() {
+    let src = r#"
+fn main() -> Unit {
+  let c: Bool = true;
+  let k: secret Int = 1;
+  if (c) {
+    wipe(k);
+    return;
+  }
+  wipe(k);
+  return;
+}
+"#;
+    assert_eq!(codes(src), Vec::<String>::new());
+}
+
+#[test]
+fn reassignment_on_one_branch_leaves_maybe_move() {
+    let src = r#"
+fn main() -> Unit {
+  let c: Bool = true;
+  let mut k: secret Int = 1;
+  wipe(k);
+  if (c) {
+    k = 2;
+  }
+  k;
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["use-after-maybe-move"]);
+}
+
+#[test]
+fn partial_move_on_one_branch_is_maybe_move() {
+    let src = r#"
+struct KeyPair { pub_key: Int, priv_key: secret Int }
+fn main() -> Unit {
+  let c: Bool = true;
+  let kp = KeyPair { pub_key: 1, priv_key: 2 };
+  if (c) {
+    wipe(kp.priv_key);
+  }
+  let p: Int = kp.pub_key;
+  wipe(kp.priv_key);
+  return;
+}
+"#;
+    let d = diags(src);
+    assert_eq!(d.len(), 1);
+    assert_eq!(d[0].0, "use-after-maybe-move");
+    assert_eq!(
+        d[0].1,
+        "Use of `kp.priv_key` after it was moved on some paths."
+    );
+}
+
+#[test]
+fn match_arms_are_joined_like_branches() {
+    let src = r#"
+enum Mode { Fast, Slow }
+fn main() -> Unit {
+  let m = Mode::Fast;
+  let k: secret Int = 1;
+  match m {
+    Mode::Fast => { wipe(k); }
+    Mode::Slow => { }
+  }
+  k;
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["use-after-maybe-move"]);
+}
@@ -0,0 +1,162 @@
+use candy_parser::parse_program;
+use candy_typecheck::typecheck;
+
+fn codes(src: &str) -> Vec<String> {
+    let p = parse_program(src).expect("parse ok");
+    match typecheck(&p) {
+        Ok(()) => vec![],
+        Err(r) => r.diagnostics.into_iter().map(|d| d.code).collect(),
+    }
+}
+
+const SWAP: &str = "fn swap<T>(a: T, b: T) -> T { wipe(a); return b; }\n";
+
+#[test]
+fn one_helper_serves_public_and_secret_values() {
+    let src = format!(
+        "{SWAP}{}",
+        r#"
+fn main() -> Unit {
+  let x: Int = swap(1, 2);
+  let k: secret Int = 3;
+  let j: secret Int = 4;
+  let s: secret Int = swap(move(k), move(j));
+  wipe(s);
+  return;
+}
+"#
+    );
+    assert!(codes(&src).is_empty(), "{:?}", codes(&src));
+}
+
+#[test]
+fn arguments_must_agree_on_the_type_parameter() {
+    let src = format!(
+        "{SWAP}{}",
+        "fn main() -> Unit { let x: Int = swap(1, true); return; }"
+    );
+    assert_eq!(codes(&src), vec!["arg-type-mismatch"]);
+}
+
+#[test]
+fn result_type_is_the_instantiated_parameter() {
+    let src = format!(
+        "{SWAP}{}",
+        "fn main() -> Unit { let x = swap(true, false); let y: Int = x; return; }"
+    );
+    assert_eq!(codes(&src), vec!["type-mismatch"]);
+}
+
+#[test]
+fn secret_instantiation_keeps_linear_rules() {
+    let src = format!(
+        "{SWAP}{}",
+        r#"
+fn main() -> Unit {
+  let k: secret Int = 3;
+  let j: secret Int = 4;
+  let s = swap(k, move(j));
+  if (s == 0) { }
+  let t: secret Int = move(k);
+  let u: secret Int = move(j);
+  wipe(s);
+  wipe(t);
+  return;
+}
+"#
+    );
+    assert_eq!(
+        codes(&src),
+        vec!["secret-copy", "secret-branch", "use-after-move"]
+    );
+}
+
+#[test]
+fn unbounded_parameter_is_move_only_in_the_body() {
+    let src = r#"
+fn dup<T>(a: T) -> T { let b: T = a; wipe(b); return a; }
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(codes(src), vec!["secret-copy"]);
+}
+
+#[test]
+fn unbounded_parameter_messages_say_it_may_be_secret() {
+    let src = r#"
+fn first<T>(a: T, b: T) -> T { return move(a); }
+fn dup<T>(a: T) -> T { let b: T = a; wipe(b); return a; }
+fn main() -> Unit { return; }
+"#;
+    let p = parse_program(src).expect("parse ok");
+    let messages: Vec<String> = typecheck(&p)
+        .unwrap_err()
+        .diagnostics
+        .into_iter()
+        .map(|d| d.message)
+        .collect();
+    assert_eq!(
+        messages,
+        vec![
+            "`b` goes out of scope holding a value of type parameter `T`, which may be secret and must be consumed; move, return or pass it on, or destroy it with `wipe(b)`.",
+            "Value `a` of type parameter `T` may be secret and cannot be copied. Use move(a) to transfer ownership.",
+        ]
+    );
+}
+
+#[test]
+fn parameter_values_cannot_be_compared() {
+    let src = r#"
+fn same<T: Copy>(a: T, b: T) -> Bool { return a == b; }
+fn main() -> Unit { return; }
+"#;
+    let p = parse_program(src).expect("parse ok");
+    let d = &typecheck(&p).unwrap_err().diagnostics[0];
+    assert_eq!(d.code, "op-type-mismatch");
+    assert_eq!(
+        d.message,
+        "Equality is not defined on generic type parameters; `==` cannot compare values of type `T`."
+    );
+}
+
+#[test]
+fn copy_bound_allows_copies_but_rejects_secrets() {
+    let src = r#"
+fn dup<T: Copy>(a: T) -> T { let b: T = a; return a; }
+fn main() -> Unit {
+  let x: Int = dup(1);
+  let k: secret Int = 2;
+  let y = dup(move(k));
+  wipe(y);
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["bound-not-satisfied"]);
+}
+
+#[test]
+fn copy_bound_rejects_move_only_structs() {
+    let src = r#"
+struct Key { k: secret Int }
+fn dup<T: Copy>(a: T) -> T { return a; }
+fn main() -> Unit {
+  let key: Key = Key { k: 1 };
+  let y = dup(move(key));
+  wipe(y);
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["bound-not-satisfied"]);
+}
+
+#[test]
+fn unknown_bound_and_duplicate_parameter_are_reported() {
+    let src = r#"
+fn f<T: Ord, T>(a: T) -> Unit { wipe(a); return; }
+
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(
+        codes(src),
+        vec!["generic-unknown-bound", "generic-duplicate"]
+    );
+}
@@ -0,0 +1,147 @@
+use candy_parser::{parse_file_with_report, parse_program};
+use candy_typecheck::typecheck;
+
+fn diags(src: &str) -> Vec<(String, String)> {
+    let p = parse_program(src).expect("parse ok");
+    match typecheck(&p) {
+        Ok(()) => vec![],
+        Err(r) => r
+            .diagnostics
+            .into_iter()
+            .map(|d| (d.code, d.message))
+            .collect(),
+    }
+}
+
+fn codes(src: &str) -> Vec<String> {
+    diags(src).into_iter().map(|(c, _)| c).collect()
+}
+
+#[test]
+fn unannotated_let_gets_the_type_of_its_initializer() {
+    let src = r#"
+fn main() -> Unit {
+  let x = 1 + 2;
+  let b: Bool = x;
+  return;
+}
+"#;
+    let d = diags(src);
+    assert_eq!(d.len(), 1, "{d:?}");
+    assert_eq!(d[0].0, "type-mismatch");
+    assert_eq!(d[0].1, "Type mismatch: expected Bool, got Int.");
+}
+
+#[test]
+fn inferred_struct_type_reaches_field_reads() {
+    let src = r#"
+struct P { a: Int }
+fn make() -> P { return P { a: 1 }; }
+fn main() -> Unit {
+  let p = make();
+  let k = p.a;
+  if (k) { return; }
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["if-cond-not-bool"]);
+}
+
+#[test]
+fn inferred_bindings_are_checked_on_assignment() {
+    let src = r#"
+fn main() -> Unit {
+  let mut n = 0;
+  n = true;
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["type-mismatch"]);
+}
+
+#[test]
+fn errors_do_not_cascade_through_bindings() {
+    let src = r#"
+fn main() -> Unit {
+  let x = nope();
+  let y: Bool = x;
+  let z: Int = x + 1;
+  if (x) { return; }
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["name-unknown"]);
+}
+
+#[test]
+fn binding_with_parse_error_is_not_ambiguous() {
+    let src = "fn main() -> Unit { let x = ; let y: Int = x; return; }";
+    let (p, parse) = parse_file_with_report("main.candy", src);
+    assert_eq!(parse.diagnostics.len(), 1);
+    assert!(typecheck(&p).is_ok());
+}
+
+#[test]
+fn unsolved_binding_is_ambiguous() {
+    let src = r#"
+fn mk<T>() -> T { return mk(); }
+fn main() -> Unit {
+  let x = mk();
+  return;
+}
+"#;
+    assert_eq!(
+        diags(src),
+        vec![(
+            "type-ambiguous".to_string(),
+            "Cannot infer the type of `x`; add a type annotation.".to_string()
+        )]
+    );
+}
+
+#[test]
+fn unsolved_expression_statement_is_ambiguous() {
+    let src = r#"
+fn mk<T>() -> T { return mk(); }
+fn main() -> Unit {
+  mk();
+  return;
+}
+"#;
+    assert_eq!(
+        diags(src),
+        vec![(
+            "type-ambiguous".to_string(),
+            "Cannot infer the type of this expression; bind it with an annotated `let`."
+                .to_string()
+        )]
+    );
+}
+
+#[test]
+fn later_uses_solve_bindings_and_statements() {
+    let src = r#"
+fn mk<T>() -> T { return mk(); }
+fn main() -> Unit {
+  let x = mk();
+  x;
+  let y: Int = x;
+  let z: Int = mk();
+  return;
+}
+"#;
+    assert!(codes(src).is_empty(), "{:?}", codes(src));
+}
+
+#[test]
+fn unsolved_variable_is_reported_once() {
+    let src = r#"
+fn mk<T>() -> T { return mk(); }
+fn main() -> Unit {
+  let x = mk();
+  x;
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["type-ambiguous"]);
+}
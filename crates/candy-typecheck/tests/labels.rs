@@ -0,0 +1,251 @@
+use candy_parser::parse_program;
+use candy_typecheck::typecheck;
+
+fn diags(src: &str) -> Vec<(String, String)> {
+    let p = parse_program(src).expect("parse ok");
+    match typecheck(&p) {
+        Ok(()) => vec![],
+        Err(r) => r
+            .diagnostics
+            .into_iter()
+            .map(|d| (d.code, d.message))
+            .collect(),
+    }
+}
+
+fn codes(src: &str) -> Vec<String> {
+    diags(src).into_iter().map(|(c, _)| c).collect()
+}
+
+const ORDER: &str = "label public < internal < secret < topsecret;\n";
+
+fn with_order(body: &str) -> String {
+    format!("{ORDER}{body}\nfn main() -> Unit {{ return; }}\n")
+}
+
+#[test]
+fn flows_upward_are_allowed() {
+    let src = with_order(
+        r#"
+fn keep(x: labeled<secret> Int) -> labeled<secret> Int { return x; }
+fn f() -> Unit {
+  let a: labeled<internal> Int = 1;
+  let s: labeled<secret> Int = keep(move(a));
+  wipe(s);
+  return;
+}
+"#,
+    );
+    assert_eq!(codes(&src), Vec::<String>::new());
+}
+
+#[test]
+fn flow_to_a_lower_parameter_is_rejected() {
+    let src = with_order(
+        r#"
+fn store(x: labeled<internal> Int) -> Unit { wipe(x); return; }
+fn f() -> Unit {
+  let s: labeled<secret> Int = 1;
+  store(move(s));
+  return;
+}
+"#,
+    );
+    let d = diags(&src);
+    assert_eq!(d.len(), 1);
+    assert_eq!(d[0].0, "label-flow");
+    assert_eq!(
+        d[0].1,
+        "Data labeled `secret` flows into parameter 1 of `store`, which is labeled `internal`; only `declassify` may lower a label."
+    );
+}
+
+#[test]
+fn annotated_let_does_not_lower_a_label() {
+    let src = with_order(
+        r#"
+fn f(ts: labeled<topsecret> Int) -> Unit {
+  let x: labeled<internal> Int = move(ts);
+  wipe(x);
+  return;
+}
+"#,
+    );
+    assert_eq!(
+        diags(&src),
+        vec![(
+            "label-flow".to_string(),
+            "Data labeled `topsecret` flows into `x`, which is labeled `internal`; only `declassify` may lower a label.".to_string()
+        )]
+    );
+}
+
+#[test]
+fn assignment_does_not_raise_a_declared_label() {
+    let src = with_order(
+        r#"
+fn f(t: labeled<topsecret> Int) -> Unit {
+  let mut m: labeled<internal> Int = 1;
+  wipe(m);
+  m = move(t);
+  wipe(m);
+  return;
+}
+"#,
+    );
+    assert_eq!(
+        diags(&src),
+        vec![(
+            "label-flow".to_string(),
+            "Data labeled `topsecret` flows into `m`, which is labeled `internal`; only `declassify` may lower a label.".to_string()
+        )]
+    );
+}
+
+#[test]
+fn operators_join_labels() {
+    let src = with_order(
+        r#"
+fn f() -> labeled<internal> Int {
+  let a: labeled<internal> Int = 1;
+  let b: labeled<secret> Int = 2;
+  return move(a) + move(b);
+}
+"#,
+    );
+    let d = diags(&src);
+    assert_eq!(d.len(), 1);
+    assert_eq!(d[0].0, "label-flow");
+    assert!(d[0].1.contains("the return value of `f`"), "{}", d[0].1);
+}
+
+#[test]
+fn secret_is_the_top_label() {
+    let src = with_order(
+        r#"
+fn store(x: labeled<secret> Int) -> Unit { wipe(x); return; }
+fn f(k: secret Int) -> Unit {
+  store(move(k));
+  return;
+}
+"#,
+    );
+    let d = diags(&src);
+    assert_eq!(d.len(), 1);
+    assert!(d[0].1.starts_with("Data labeled `topsecret`"), "{}", d[0].1);
+}
+
+#[test]
+fn labeled_data_follows_the_secret_rules() {
+    let src = with_order(
+        r#"
+fn f() -> Unit {
+  let a: labeled<internal> Int = 1;
+  let b = a;
+  if (b == 0) { return; }
+  return;
+}
+"#,
+    );
+    assert_eq!(
+        codes(&src),
+        vec!["secret-copy", "secret-branch", "secret-not-consumed"]
+    );
+}
+
+#[test]
+fn flow_to_public_keeps_the_leak_codes() {
+    let src = with_order(
+        r#"
+fn show(x: Int) -> Unit { return; }
+fn f() -> Int {
+  let a: labeled<internal> Int = 1;
+  show(move(a));
+  let b: labeled<internal> Int = 2;
+  return move(b);
+}
+"#,
+    );
+    assert_eq!(codes(&src), vec!["secret-leak-param", "secret-leak-return"]);
+}
+
+#[test]
+fn log_rejects_labeled_data() {
+    let src = with_order(
+        r#"
+fn f(t: labeled<internal> Str) -> Unit effects(io) {
+  let s: secret Str = "k";
+  log(s);
+  log(move(t));
+  wipe(s);
+  return;
+}
+"#,
+    );
+    assert_eq!(codes(&src), vec!["secret-leak-param", "secret-leak-param"]);
+}
+
+#[test]
+fn declassify_lowers_any_label() {
+    let src = with_order(
+        r#"
+fn f() -> Int effects(declassify) {
+  let a: labeled<internal> Int = 1;
+  return declassify(a, "tenant count is public");
+}
+"#,
+    );
+    assert_eq!(codes(&src), Vec::<String>::new());
+}
+
+#[test]
+fn public_field_takes_the_higher_label() {
+    let src = with_order(
+        r#"
+struct Row { id: Int, tenant: labeled<internal> Int }
+fn store(x: labeled<internal> Int) -> Unit { wipe(x); return; }
+fn f() -> Unit {
+  let s: labeled<secret> Int = 1;
+  let row: Row = Row { id: move(s), tenant: 2 };
+  store(move(row.tenant));
+  wipe(row.id);
+  return;
+}
+"#,
+    );
+    assert_eq!(codes(&src), vec!["label-flow"]);
+}
+
+#[test]
+fn unknown_label_is_reported() {
+    let src = with_order("fn f(x: labeled<tenant> Int) -> Unit { return; }");
+    let d = diags(&src);
+    assert_eq!(d.len(), 1);
+    assert_eq!(d[0].0, "label-unknown");
+    assert!(
+        d[0].1.contains("public < internal < secret < topsecret"),
+        "{}",
+        d[0].1
+    );
+}
+
+#[test]
+fn default_order_is_public_and_secret() {
+    let src = r#"
+fn store(x: labeled<secret> Int) -> Unit { wipe(x); return; }
+fn f(k: secret Int) -> Unit { store(move(k)); return; }
+fn g(x: labeled<internal> Int) -> Unit { return; }
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(codes(src), vec!["label-unknown"]);
+}
+
+#[test]
+fn duplicate_labels_are_reported() {
+    let src = r#"
+label public < internal < internal;
+label public < secret;
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(codes(src), vec!["label-duplicate", "label-duplicate"]);
+}
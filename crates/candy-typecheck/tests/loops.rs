@@ -0,0 +1,126 @@
+use candy_parser::parse_program;
+use candy_typecheck::typecheck;
+
+fn codes(src: &str) -> Vec<String> {
+    let p = parse_program(src).expect("parse ok");
+    match typecheck(&p) {
+        Ok(()) => vec![],
+        Err(r) => r.diagnostics.into_iter().map(|d| d.code).collect(),
+    }
+}
+
+#[test]
+fn constant_bounds_pass() {
+    let src = r#"
+fn main() -> Unit {
+  for i in 0..2 * 8 {
+    let k: Int = i + 1;
+  }
+  return;
+}
+"#;
+    assert!(codes(src).is_empty(), "{:?}", codes(src));
+}
+
+#[test]
+fn runtime_bound_is_rejected() {
+    let src = r#"
+fn main() -> Unit {
+  let n: Int = 4;
+  for i in 0..n { }
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["loop-bound-not-constant"]);
+}
+
+#[test]
+fn secret_bound_is_rejected() {
+    let src = r#"
+fn main() -> Unit {
+  let n: secret Int = 4;
+  for i in 0..n + 1 { }
+  wipe(n);
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["secret-loop-bound"]);
+}
+
+#[test]
+fn non_int_bound_is_type_error() {
+    let src = "fn main() -> Unit { for i in 0..true { } return; }";
+    assert_eq!(codes(src), vec!["type-mismatch"]);
+}
+
+#[test]
+fn moving_outer_variable_in_loop_is_rejected() {
+    let src = r#"
+fn main() -> Unit {
+  let k: secret Int = 1;
+  for i in 0..3 {
+    wipe(k);
+  }
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["move-in-loop"]);
+}
+
+#[test]
+fn moving_loop_local_is_fine() {
+    let src = r#"
+fn main() -> Unit {
+  for i in 0..3 {
+    let k: secret Int = 1;
+    wipe(k);
+  }
+  return;
+}
+"#;
+    assert!(codes(src).is_empty(), "{:?}", codes(src));
+}
+
+#[test]
+fn secret_branch_inside_loop_is_still_rejected() {
+    let src = r#"
+fn main() -> Unit {
+  let s: secret Bool = true;
+  for i in 0..3 {
+    if (s) { }
+  }
+  wipe(s);
+  return;
+
+}
+"#;
+    assert_eq!(codes(src), vec!["secret-branch"]);
+}
+
+#[test]
+fn loop_variable_is_scoped_to_body() {
+    let src = r#"
+fn main() -> Unit {
+  for i in 0..3 { }
+  let j: Int = i;
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["name-unknown"]);
+}
+
+#[test]
+fn zero_length_loop_body_may_not_run() {
+    let src = r#"
+fn main() -> Unit {
+  let mut k: secret Int = 1;
+  wipe(k);
+  for i in 0..0 {
+    k = 2;
+  }
+  wipe(k);
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["use-after-maybe-move"]);
+}
@@ -0,0 +1,226 @@
+use candy_diagnostics::Severity;
+use candy_parser::parse_program;
+use candy_typecheck::{typecheck, typecheck_with_warnings};
+
+fn codes(src: &str) -> Vec<String> {
+    let p = parse_program(src).expect("parse ok");
+    match typecheck(&p) {
+        Ok(()) => vec![],
+        Err(r) => r.diagnostics.into_iter().map(|d| d.code).collect(),
+    }
+}
+
+const OUTCOME: &str = "enum Outcome { Ok(Int), Denied }\n";
+
+fn with_outcome(body: &str) -> String {
+    format!("{OUTCOME}fn main() -> Unit effects(io) {{\n{body}\n  return;\n}}\n")
+}
+
+#[test]
+fn exhaustive_match_with_binding() {
+    let src = with_outcome(
+        r#"
+  let o: Outcome = Outcome::Ok(1);
+  match o {
+    Outcome::Ok(n) => { let m: Int = n + 1; }
+    Outcome::Denied => { log("denied"); }
+  }
+"#,
+    );
+    assert!(codes(&src).is_empty(), "{:?}", codes(&src));
+}
+
+#[test]
+fn missing_variant_is_non_exhaustive() {
+    let src = with_outcome(
+        r#"
+  let o: Outcome = Outcome::Denied;
+  match o {
+    Outcome::Ok(n) => { }
+  }
+"#,
+    );
+    assert_eq!(codes(&src), vec!["match-non-exhaustive"]);
+}
+
+#[test]
+fn wildcard_makes_match_exhaustive() {
+    let src = with_outcome(
+        r#"
+  let o: Outcome = Outcome::Denied;
+  match o {
+    Outcome::Denied => { }
+    _ => { }
+  }
+"#,
+    );
+    assert!(codes(&src).is_empty(), "{:?}", codes(&src));
+}
+
+#[test]
+fn redundant_arm_is_reported_on_a_valid_program() {
+    let src = with_outcome(
+        r#"
+  let o: Outcome = Outcome::Denied;
+  match o {
+    Outcome::Ok(n) => { }
+    Outcome::Denied => { }
+    _ => { }
+  }
+"#,
+    );
+    let p = parse_program(&src).expect("parse ok");
+    let r = typecheck_with_warnings(&p);
+    assert!(r.is_ok());
+    let warnings: Vec<_> = r
+        .diagnostics
+        .iter()
+        .map(|d| (d.code.as_str(), d.severity))
+        .collect();
+    assert_eq!(warnings, vec![("match-unreachable-arm", Severity::Warning)]);
+}
+
+#[test]
+fn bool_match_needs_both_arms() {
+    let src = with_outcome(
+        r#"
+  let b: Bool = true;
+  match b {
+    true => { }
+  }
+"#,
+    );
+    assert_eq!(codes(&src), vec!["match-non-exhaustive"]);
+}
+
+#[test]
+fn binding_has_payload_type() {
+    let src = with_outcome(
+        r#"
+  let o: Outcome = Outcome::Ok(1);
+  match o {
+    Outcome::Ok(n) => { let b: Bool = n; }
+    Outcome::Denied => { }
+  }
+"#,
+    );
+    assert_eq!(codes(&src), vec!["type-mismatch"]);
+}
+
+#[test]
+fn binding_is_scoped_to_its_arm() {
+    let src = with_outcome(
+        r#"
+  let o: Outcome = Outcome::Ok(1);
+  match o {
+    Outcome::Ok(n) => { }
+    Outcome::Denied => { let m: Int = n; }
+  }
+"#,
+    );
+    assert_eq!(codes(&src), vec!["name-unknown"]);
+}
+
+#[test]
+fn secret_scrutinee_is_secret_branch() {
+    let src = with_outcome(
+        r#"
+  let o: secret Outcome = Outcome::Denied;
+  match move(o) {
+    Outcome::Ok(n) => { wipe(n); }
+    Outcome::Denied => { }
+  }
+"#,
+    );
+    assert_eq!(codes(&src), vec!["secret-branch"]);
+}
+
+#[test]
+fn variant_built_from_secret_is_secret() {
+    let src = with_outcome(
+        r#"
+  let s: secret Int = 3;
+  let o: Outcome = Outcome::Ok(move(s));
+  match o {
+    Outcome::Ok(n) => { wipe(n); }
+    Outcome::Denied => { }
+  }
+  wipe(o);
+"#,
+    );
+    assert_eq!(codes(&src), vec!["secret-branch"]);
+}
+
+#[test]
+fn secret_payload_must_be_moved_out() {
+    let src = r#"
+enum Slot { Key(secret Int), Empty }
+fn main() -> Unit {
+  let a: Slot = Slot::Key(1);
+  match a {
+    Slot::Key(k) => { wipe(k); }
+    Slot::Empty => { }
+  }
+  let b: Slot = Slot::Key(2);
+  match move(b) {
+    Slot::Key(k) => { wipe(k); }
+    Slot::Empty => { }
+  }
+  let c: Slot = Slot::Empty;
+  match c {
+    Slot::Key(_) => { }
+    Slot::Empty => { }
+  }
+  wipe(c);
+  return;
+
+}
+"#;
+    assert_eq!(codes(src), vec!["secret-copy"]);
+}
+
+#[test]
+fn pattern_and_constructor_errors() {
+    let src = r#"
+enum Outcome { Ok(Int), Denied }
+enum Other { A }
+fn main() -> Unit {
+  let o: Outcome = Outcome::Ok(true);
+  let p: Outcome = Outcome::Ok;
+  match o {
+    Other::A => { }
+    Outcome::Ok => { }
+    Outcome::Denied => { }
+    _ => { }
+  }
+  match 1 { _ => { } }
+  return;
+}
+"#;
+    assert_eq!(
+        codes(src),
+        vec![
+            "arg-type-mismatch",
+            "call-arity",
+            "pattern-mismatch",
+            "pattern-arity",
+            "match-unreachable-arm",
+            "type-mismatch"
+        ]
+    );
+}
+
+#[test]
+fn enum_declaration_errors() {
+    let src = r#"
+struct E { x: Int }
+enum E { B }
+enum F { A, A }
+enum Chain { Link(Chain), End }
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(
+        codes(src),
+        vec!["enum-duplicate", "enum-duplicate-variant", "enum-recursive"]
+    );
+}
@@ -0,0 +1,148 @@
+use candy_parser::parse_program;
+use candy_typecheck::typecheck;
+
+fn codes(src: &str) -> Vec<String> {
+    let p = parse_program(src).expect("parse ok");
+    match typecheck(&p) {
+        Ok(()) => vec![],
+        Err(r) => r.diagnostics.into_iter().map(|d| d.code).collect(),
+    }
+}
+
+fn fixes(src: &str) -> Vec<Option<(String, String)>> {
+    let p = parse_program(src).expect("parse ok");
+    typecheck(&p)
+        .unwrap_err()
+        .diagnostics
+        .into_iter()
+        .map(|d| d.fix.map(|f| (f.replace, f.with)))
+        .collect()
+}
+
+#[test]
+fn mutable_binding_can_be_reassigned() {
+    let src = r#"
+fn main() -> Unit {
+  let mut n: Int = 0;
+  n = n + 1;
+  return;
+}
+"#;
+    assert!(codes(src).is_empty(), "{:?}", codes(src));
+}
+
+#[test]
+fn assigning_immutable_binding_is_rejected() {
+    let src = r#"
+fn main() -> Unit {
+  let n: Int = 0;
+  n = 1;
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["assign-immutable"]);
+}
+
+#[test]
+fn assignment_type_must_match() {
+    let src = r#"
+fn main() -> Unit {
+  let mut n: Int = 0;
+  n = true;
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["type-mismatch"]);
+}
+
+#[test]
+fn overwriting_live_secret_is_rejected() {
+    let src = r#"
+fn main() -> Unit {
+  let mut k: secret Int = 1;
+  k = 2;
+  wipe(k);
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["secret-overwrite"]);
+}
+
+#[test]
+fn assigning_moved_variable_revives_it() {
+    let src = r#"
+fn main() -> Unit {
+  let mut k: secret Int = 1;
+  wipe(k);
+  k = 2;
+  wipe(k);
+  return;
+}
+"#;
+    assert!(codes(src).is_empty(), "{:?}", codes(src));
+}
+
+#[test]
+fn secret_accumulator_in_loop() {
+    let src = r#"
+fn main() -> Unit {
+  let mut acc: secret Int = 0;
+  for i in 0..4 {
+    acc = move(acc) + i;
+  }
+  wipe(acc);
+  return;
+}
+"#;
+    assert!(codes(src).is_empty(), "{:?}", codes(src));
+}
+
+#[test]
+fn assigning_copied_secret_is_rejected() {
+    let src = r#"
+fn main() -> Unit {
+  let s: secret Int = 1;
+  let mut k: secret Int = 0;
+  wipe(k);
+  k = s;
+  wipe(k);
+  return;
+
+}
+"#;
+    assert_eq!(codes(src), vec!["secret-copy"]);
+}
+
+#[test]
+fn let_mut_fix_only_for_let_bindings() {
+    let src = r#"
+fn main() -> Unit {
+  let n: Int = 0;
+  n = 1;
+  for i in 0..2 {
+    i = 3;
+  }
+  return;
+}
+"#;
+    assert_eq!(
+        fixes(src),
+        vec![Some(("let n ".to_string(), "let mut n ".to_string())), None]
+    );
+}
+
+#[test]
+fn overwrite_fix_wipes_the_old_secret() {
+    let src = r#"
+fn main() -> Unit {
+  let mut k: secret Int = 1;
+  k = 2;
+  wipe(k);
+  return;
+}
+"#;
+    assert_eq!(
+        fixes(src),
+        vec![Some(("k = ".to_string(), "wipe(k);\nk = ".to_string()))]
+    );
+}
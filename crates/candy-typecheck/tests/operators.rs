@@ -0,0 +1,93 @@
+use candy_parser::parse_program;
+use candy_typecheck::typecheck;
+
+fn codes(src: &str) -> Vec<String> {
+    let p = parse_program(src).expect("parse ok");
+    match typecheck(&p) {
+        Ok(()) => vec![],
+        Err(r) => r.diagnostics.into_iter().map(|d| d.code).collect(),
+    }
+}
+
+#[test]
+fn well_typed_operators_pass() {
+    let src = r#"
+fn main() -> Unit {
+  let n: Int = 1 + 2 * 3 - 4 / 2 % 3;
+  let m = -n;
+  let b: Bool = n < m && !(m >= 3) || n == m;
+  let s: Bool = "a" != "b";
+  return;
+}
+"#;
+    assert!(codes(src).is_empty(), "{:?}", codes(src));
+}
+
+#[test]
+fn arithmetic_on_bool_is_error() {
+    let src = "fn main() -> Unit { let x = true + 1; return; }";
+    assert_eq!(codes(src), vec!["op-type-mismatch"]);
+}
+
+#[test]
+fn logic_on_int_is_error() {
+    let src = "fn main() -> Unit { let x = 1 && 2; let y = !3; return; }";
+    assert_eq!(codes(src), vec!["op-type-mismatch", "op-type-mismatch"]);
+}
+
+#[test]
+fn equality_needs_same_types() {
+    let src = "fn main() -> Unit { let x = 1 == true; return; }";
+    assert_eq!(codes(src), vec!["op-type-mismatch"]);
+}
+
+#[test]
+fn comparison_yields_bool() {
+    let src = "fn main() -> Unit { let x: Int = 1 < 2; return; }";
+    assert_eq!(codes(src), vec!["type-mismatch"]);
+}
+
+#[test]
+fn secret_comparison_still_triggers_secret_branch() {
+    let src = r#"
+fn main() -> Unit {
+  let s: secret Int = 1;
+  if (s == 0) { }
+  wipe(s);
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["secret-branch"]);
+}
+
+#[test]
+fn secret_plus_public_stays_secret() {
+    let src = r#"
+fn main() -> Unit {
+  let s: secret Int = 1;
+  let t = s + 1;
+  let u: Int = t * 2;
+  if (u > 0) { }
+  wipe(s);
+  wipe(t);
+  wipe(u);
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["secret-branch"]);
+}
+
+#[test]
+fn operator_reads_are_not_secret_copies() {
+    let src = r#"
+fn main() -> Unit {
+  let s: secret Int = 1;
+  let t: secret Int = s + 1;
+  wipe(s);
+  wipe(t);
+  return;
+
+}
+"#;
+    assert!(codes(src).is_empty(), "{:?}", codes(src));
+}
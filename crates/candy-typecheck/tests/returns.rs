@@ -0,0 +1,121 @@
+use candy_diagnostics::Severity;
+use candy_parser::parse_program;
+use candy_typecheck::{typecheck, typecheck_with_warnings};
+
+fn diags(src: &str) -> Vec<(String, Severity)> {
+    let p = parse_program(src).expect("parse ok");
+    match typecheck(&p) {
+        Ok(()) => vec![],
+        Err(r) => r
+            .diagnostics
+            .into_iter()
+            .map(|d| (d.code, d.severity))
+            .collect(),
+    }
+}
+
+fn codes(src: &str) -> Vec<String> {
+    diags(src).into_iter().map(|(c, _)| c).collect()
+}
+
+#[test]
+fn empty_non_unit_body_is_missing_return() {
+    let src = "fn f() -> Int { }\nfn main() -> Unit { return; }";
+    assert_eq!(codes(src), vec!["missing-return"]);
+}
+
+#[test]
+fn return_on_one_branch_only_is_missing_return() {
+    let src = r#"
+fn f(c: Bool) -> Int {
+  if (c) { return 1; }
+}
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(codes(src), vec!["missing-return"]);
+}
+
+#[test]
+fn returns_on_every_path_are_accepted() {
+    let src = r#"
+enum Mode { Fast, Slow }
+fn f(c: Bool) -> Int {
+  if (c) { return 1; } else { return 2; }
+}
+fn g(m: Mode) -> Int {
+  match m {
+    Mode::Fast => { return 1; }
+    Mode::Slow => { return 2; }
+  }
+}
+fn main() -> Unit { }
+"#;
+    assert_eq!(codes(src), Vec::<String>::new());
+}
+
+#[test]
+fn return_inside_loop_does_not_count() {
+    let src = r#"
+fn f() -> Int {
+  for i in 0..4 { return i; }
+}
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(codes(src), vec!["missing-return"]);
+}
+
+#[test]
+fn statements_after_return_are_unreachable() {
+    let src = r#"
+fn main() -> Unit {
+  return;
+  let x: Int = 1;
+  let y: Int = 2;
+}
+fn f() -> Int { }
+"#;
+    assert_eq!(
+        diags(src),
+        vec![
+            ("unreachable-code".to_string(), Severity::Warning),
+            ("missing-return".to_string(), Severity::Error)
+        ]
+    );
+}
+
+#[test]
+fn unreachable_code_alone_is_only_a_warning() {
+    let src = "fn main() -> Unit { return; return; }";
+    let p = parse_program(src).expect("parse ok");
+    assert!(typecheck(&p).is_ok());
+    let r = typecheck_with_warnings(&p);
+    let found: Vec<_> = r
+        .diagnostics
+        .iter()
+        .map(|d| (d.code.as_str(), d.severity))
+        .collect();
+    assert_eq!(found, vec![("unreachable-code", Severity::Warning)]);
+}
+
+#[test]
+fn statements_after_diverging_if_are_unreachable() {
+    let src = r#"
+fn f(c: Bool) -> Int {
+  if (c) { return 1; } else { return 2; }
+  return 3;
+}
+fn main() -> Unit { return 1; }
+"#;
+    assert_eq!(codes(src), vec!["unreachable-code", "return-mismatch"]);
+}
+
+#[test]
+fn unreachable_code_is_still_checked() {
+    let src = r#"
+fn main() -> Unit {
+  return;
+  let x: Int = true;
+}
+"#;
+    assert_eq!(codes(src), vec!["unreachable-code", "type-mismatch"]);
+}
@@ -0,0 +1,139 @@
+use candy_parser::parse_program;
+use candy_typecheck::typecheck;
+
+fn diags(src: &str) -> Vec<(String, String)> {
+    let p = parse_program(src).expect("parse ok");
+    match typecheck(&p) {
+        Ok(()) => vec![],
+        Err(r) => r
+            .diagnostics
+            .into_iter()
+            .map(|d| (d.code, d.message))
+            .collect(),
+    }
+}
+
+fn codes(src: &str) -> Vec<String> {
+    diags(src).into_iter().map(|(c, _)| c).collect()
+}
+
+#[test]
+fn let_in_block_is_out_of_scope_afterwards() {
+    let src = r#"
+fn main() -> Unit {
+  let c: Bool = true;
+  if (c) {
+    let x: Int = 1;
+  }
+  let y: Int = x;
+  return;
+}
+"#;
+    let d = diags(src);
+    assert_eq!(d.len(), 1);
+    assert_eq!(d[0].0, "name-out-of-scope");
+    assert!(d[0].1.contains("`x`"), "{}", d[0].1);
+}
+
+#[test]
+fn loop_body_bindings_do_not_escape() {
+    let src = r#"
+fn main() -> Unit {
+  for i in 0..4 {
+    let t: Int = i;
+  }
+  t = 2;
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["name-out-of-scope"]);
+}
+
+#[test]
+fn never_declared_name_is_still_unknown() {
+    let src = r#"
+fn main() -> Unit {
+  let y: Int = z;
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["name-unknown"]);
+}
+
+#[test]
+fn inner_shadow_ends_with_its_block() {
+    let src = r#"
+fn main() -> Unit {
+  let c: Bool = true;
+  let x: Int = 1;
+  if (c) {
+    let x: Bool = true;
+    let b: Bool = x;
+  }
+  let y: Int = x;
+  return;
+}
+"#;
+    assert_eq!(codes(src), Vec::<String>::new());
+}
+
+#[test]
+fn shadowing_a_live_secret_is_rejected() {
+    let src = r#"
+fn main() -> Unit {
+  let k: secret Int = 1;
+  let k: Int = 2;
+  return;
+}
+"#;
+    let d = diags(src);
+    assert_eq!(d.len(), 1);
+    assert_eq!(d[0].0, "secret-shadowed");
+    assert!(d[0].1.contains("`k`"), "{}", d[0].1);
+}
+
+#[test]
+fn shadowing_a_moved_secret_is_allowed() {
+    let src = r#"
+fn main() -> Unit {
+  let k: secret Int = 1;
+  let k: secret Int = move(k);
+  wipe(k);
+  return;
+}
+"#;
+    assert_eq!(codes(src), Vec::<String>::new());
+}
+
+#[test]
+fn secret_shadowed_in_a_nested_block() {
+    let src = r#"
+fn main() -> Unit {
+  let c: Bool = true;
+  let k: secret Int = 1;
+  if (c) {
+    let k: Int = 2;
+  }
+  wipe(k);
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["secret-shadowed"]);
+}
+
+#[test]
+fn moved_secret_may_be_shadowed_in_a_block() {
+    let src = r#"
+fn main() -> Unit {
+  let c: Bool = true;
+  let k: secret Int = 1;
+  let s: secret Int = move(k);
+  if (c) {
+    let k: Int = 2;
+  }
+  wipe(s);
+  return;
+}
+"#;
+    assert_eq!(codes(src), Vec::<String>::new());
+}
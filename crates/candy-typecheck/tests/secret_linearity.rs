@@ -36,7 +36,7 @@ fn move_ok_compiles() {
 fn main() -> Unit {
   let a: secret Int = 1;
   let b: secret Int = move(a);
-  b;
+  wipe(b);
   return;
 }
 "#;
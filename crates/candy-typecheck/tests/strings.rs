@@ -0,0 +1,55 @@
+use candy_parser::parse_program;
+use candy_typecheck::typecheck;
+
+fn codes(src: &str) -> Vec<String> {
+    let p = parse_program(src).expect("parse ok");
+    match typecheck(&p) {
+        Ok(()) => vec![],
+        Err(r) => r.diagnostics.into_iter().map(|d| d.code).collect(),
+    }
+}
+
+#[test]
+fn str_annotation_accepts_string_literal() {
+    let src = r#"
+fn main() -> Unit effects(io) {
+  let msg: Str = "line\n\"quoted\"";
+  log(msg);
+  return;
+}
+"#;
+    assert!(codes(src).is_empty());
+}
+
+#[test]
+fn str_is_not_int() {
+    let src = r#"
+fn main() -> Unit {
+  let n: Int = "ten";
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["type-mismatch"]);
+}
+
+#[test]
+fn log_requires_str_argument() {
+    let src = r#"
+fn main() -> Unit effects(io) {
+  log(42);
+  return;
+}
+"#;
+    assert_eq!(codes(src), vec!["arg-type-mismatch"]);
+}
+
+#[test]
+fn str_return_type() {
+    let src = r#"
+fn label() -> Str {
+  return r"raw\label";
+}
+fn main() -> Unit { return; }
+"#;
+    assert!(codes(src).is_empty());
+}
@@ -0,0 +1,174 @@
+use candy_parser::parse_program;
+use candy_typecheck::typecheck;
+
+fn codes(src: &str) -> Vec<String> {
+    let p = parse_program(src).expect("parse ok");
+    match typecheck(&p) {
+        Ok(()) => vec![],
+        Err(r) => r.diagnostics.into_iter().map(|d| d.code).collect(),
+    }
+}
+
+const KEYPAIR: &str = "struct KeyPair { pub_key: Int, priv_key: secret Int }\n";
+
+fn with_keypair(body: &str) -> String {
+    format!("{KEYPAIR}fn main() -> Unit {{\n{body}\n  return;\n}}\n")
+}
+
+#[test]
+fn struct_literal_and_public_field_read() {
+    let src = with_keypair(
+        r#"
+  let kp: KeyPair = KeyPair { pub_key: 1, priv_key: 2 };
+  let p: Int = kp.pub_key;
+  let q: Int = kp.pub_key + 1;
+  wipe(kp);
+"#,
+    );
+    assert!(codes(&src).is_empty(), "{:?}", codes(&src));
+}
+
+#[test]
+fn unknown_struct_type_is_reported() {
+    let src = "fn main() -> Unit { let x: Nope = 1; return; }";
+    assert_eq!(codes(src), vec!["type-unknown"]);
+}
+
+#[test]
+fn struct_with_secret_field_is_linear() {
+    let src = with_keypair(
+        r#"
+  let kp: KeyPair = KeyPair { pub_key: 1, priv_key: 2 };
+  let copy: KeyPair = kp;
+  wipe(copy);
+"#,
+    );
+    assert_eq!(codes(&src), vec!["secret-copy"]);
+}
+
+#[test]
+fn public_struct_can_be_copied() {
+    let src = r#"
+struct Point { x: Int, y: Int }
+fn main() -> Unit {
+  let p: Point = Point { x: 1, y: 2 };
+  let q: Point = p;
+  let r: Point = p;
+  return;
+}
+"#;
+    assert!(codes(src).is_empty(), "{:?}", codes(src));
+}
+
+#[test]
+fn secret_field_read_must_move() {
+    let src = with_keypair(
+        r#"
+  let kp: KeyPair = KeyPair { pub_key: 1, priv_key: 2 };
+  let s: secret Int = kp.priv_key;
+  wipe(s);
+"#,
+    );
+    assert_eq!(codes(&src), vec!["secret-copy"]);
+}
+
+#[test]
+fn partial_moves_are_tracked_per_field() {
+    let src = with_keypair(
+        r#"
+  let kp: KeyPair = KeyPair { pub_key: 1, priv_key: 2 };
+  wipe(kp.priv_key);
+  let p: Int = kp.pub_key;
+  let t: secret Int = move(kp.priv_key);
+  let whole: KeyPair = move(kp);
+"#,
+    );
+    assert_eq!(codes(&src), vec!["use-after-move", "use-after-move"]);
+}
+
+#[test]
+fn literal_field_errors() {
+    let src = with_keypair(
+        r#"
+  let a: KeyPair = KeyPair { pub_key: 1 };
+  let b: KeyPair = KeyPair { pub_key: 1, priv_key: 2, extra: 3 };
+  let c: KeyPair = KeyPair { pub_key: true, priv_key: 2 };
+  let d: Int = a.nope;
+  wipe(a);
+  wipe(b);
+  wipe(c);
+"#,
+    );
+    assert_eq!(
+        codes(&src),
+        vec![
+            "struct-missing-field",
+            "field-unknown",
+            "type-mismatch",
+            "field-unknown"
+        ]
+    );
+}
+
+#[test]
+fn secret_value_in_public_field_taints_struct() {
+    let src = with_keypair(
+        r#"
+  let s: secret Int = 7;
+  let kp: KeyPair = KeyPair { pub_key: move(s), priv_key: 2 };
+  if (kp.pub_key == 0) { }
+  wipe(kp);
+"#,
+    );
+    assert_eq!(codes(&src), vec!["secret-branch"]);
+}
+
+#[test]
+fn nested_struct_inherits_linearity() {
+    let src = format!(
+        "{KEYPAIR}{}",
+        r#"
+struct Wallet { owner: Int, keys: KeyPair }
+fn main() -> Unit {
+  let w: Wallet = Wallet { owner: 1, keys: KeyPair { pub_key: 1, priv_key: 2 } };
+  wipe(w.keys.priv_key);
+  let o: Int = w.owner;
+  let again: KeyPair = move(w.keys);
+  return;
+}
+"#
+    );
+    assert_eq!(codes(&src), vec!["use-after-move"]);
+}
+
+#[test]
+fn struct_declaration_errors() {
+    let src = r#"
+struct A { x: Int, x: Bool }
+struct A { y: Int }
+struct Loop { next: Loop }
+fn main() -> Unit { return; }
+"#;
+    assert_eq!(
+        codes(src),
+        vec![
+            "struct-duplicate",
+            "struct-duplicate-field",
+            "struct-recursive"
+        ]
+    );
+}
+
+#[test]
+fn overwriting_struct_with_live_secret_field_is_rejected() {
+    let src = with_keypair(
+        r#"
+  let mut kp: KeyPair = KeyPair { pub_key: 1, priv_key: 2 };
+  kp = KeyPair { pub_key: 3, priv_key: 4 };
+  wipe(kp.priv_key);
+  kp = KeyPair { pub_key: 5, priv_key: 6 };
+  wipe(kp);
+"#,
+    );
+    assert_eq!(codes(&src), vec!["secret-overwrite"]);
+}